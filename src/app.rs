@@ -0,0 +1,590 @@
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+use std::{fs, io, path::Path, time::Duration};
+use tui::{
+    backend::Backend,
+    layout::*,
+    text::{Span, Spans},
+    widgets::*,
+    Terminal,
+};
+use unicode_width::UnicodeWidthStr;
+use ynab_openapi::models::BudgetSummary;
+
+use crate::{
+    components::{block, contains, render_help, FuzzyFinder, Pick, TextInput},
+    config,
+    data_layer::{Payload, Request, Response, Worker},
+    event::{AppEvent, EventSource},
+    export::Format,
+    history::History,
+    keymap::{self, Action},
+    layout,
+    page::{AccountsPage, Homepage, Message},
+    theme,
+    util::find_budget,
+};
+
+/// How often a spinner turns while something is loading
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
+
+/// The tabs, each with its own page history, and the worker serving them, independent of the
+/// terminal they are drawn to
+pub struct App<B: Backend> {
+    tabs: Vec<History<B>>,
+    /// The tab on screen
+    tab: usize,
+    worker: Worker,
+    /// The budgets from the last budgets response, for `:budget`
+    budgets: Vec<BudgetSummary>,
+    /// The `:` command line, which keeps its history while closed
+    command: TextInput,
+    editing_command: bool,
+    /// Feedback from the last command
+    message: Option<String>,
+    /// Whether the help popup is open, any key closes it
+    help: bool,
+    /// The budget finder, while it is open
+    finder: Option<FuzzyFinder<BudgetSummary>>,
+    /// The page picked in the breadcrumbs, by its depth, while picking one
+    crumb: Option<usize>,
+    /// Where each page in the breadcrumbs was last drawn, to go back to the clicked one
+    crumb_areas: Vec<Rect>,
+    opened_default_budget: bool,
+}
+
+impl<B: Backend> App<B> {
+    pub fn new(mut worker: Worker) -> Self {
+        Self {
+            tabs: vec![History::new(Box::new(Homepage::new(&mut worker)))],
+            tab: 0,
+            worker,
+            budgets: vec![],
+            command: TextInput::new(),
+            editing_command: false,
+            message: None,
+            help: false,
+            finder: None,
+            crumb: None,
+            crumb_areas: vec![],
+            opened_default_budget: false,
+        }
+    }
+
+    /// Draws and handles events until a page quits or `events` runs out. The screen is only
+    /// drawn again after input or a response that changed a page, and while a spinner turns.
+    /// Otherwise the loop sleeps until the next event.
+    pub fn run(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut impl EventSource,
+    ) -> io::Result<()> {
+        let mut dirty = true;
+        while !events.is_done() {
+            let busy = self.worker.is_busy();
+            if dirty || busy {
+                self.draw(terminal)?;
+                dirty = false;
+            }
+
+            match events.next(busy.then_some(SPINNER_FRAME))? {
+                Some(AppEvent::Input(event)) => {
+                    if !self.handle_event(event)? {
+                        break;
+                    }
+                    layout::save();
+                    dirty = true;
+                }
+                Some(AppEvent::InputError(e)) => return Err(e),
+                Some(AppEvent::Response) | None => {}
+            }
+            dirty |= self.receive();
+        }
+
+        Ok(())
+    }
+
+    /// The name of the page on screen in each tab
+    pub fn tabs(&self) -> Vec<String> {
+        self.tabs.iter().map(|t| t.page().name()).collect()
+    }
+
+    /// The tab on screen, counted from 0
+    pub fn tab(&self) -> usize {
+        self.tab
+    }
+
+    /// The names of the pages from the first to the one on screen, in the current tab
+    pub fn path(&self) -> Vec<String> {
+        self.tabs[self.tab].path().map(|p| p.name()).collect()
+    }
+
+    /// The path, with the picked page highlighted while picking one
+    fn breadcrumbs(&self) -> Spans<'static> {
+        let mut spans = vec![];
+        for (depth, name) in self.path().into_iter().enumerate() {
+            if depth > 0 {
+                spans.push(Span::raw(" -> "));
+            }
+            match self.crumb {
+                Some(crumb) if crumb == depth => {
+                    spans.push(Span::styled(name, theme::get().selected_row))
+                }
+                Some(_) => spans.push(Span::raw(format!("{}:{name}", depth + 1))),
+                None => spans.push(Span::raw(name)),
+            }
+        }
+        Spans::from(spans)
+    }
+
+    pub fn draw(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        let path = self.breadcrumbs();
+        // Every other span is a page, the ones between are arrows
+        let mut x = 0;
+        let crumbs: Vec<(u16, u16)> = path
+            .0
+            .iter()
+            .map(|span| {
+                let width = span.width() as u16;
+                x += width;
+                (x - width, width)
+            })
+            .step_by(2)
+            .collect();
+        let mut crumb_areas = vec![];
+        let titles = self.tabs().into_iter().enumerate();
+        let titles = titles.map(|(i, name)| Spans::from(format!("{} {name}", i + 1)));
+        let tabs = Tabs::new(titles.collect())
+            .select(self.tab)
+            .highlight_style(theme::get().selected_row);
+        let mut status = self.worker.status().to_string();
+        if let Some(profile) = &config::get().profile {
+            status = format!("{status} [{profile}]").trim_start().to_string();
+        }
+
+        let page = self.tabs[self.tab].page_mut();
+
+        let help = self.help.then(|| [Action::GLOBAL, page.actions()].concat());
+        let command = self.editing_command.then_some(&self.command);
+        let finder = self.finder.as_ref();
+        let message = self
+            .message
+            .as_deref()
+            .map(|m| Paragraph::new(m).block(block()));
+
+        terminal.draw(|f| {
+            let chunks = Layout::default()
+                .constraints([Constraint::Percentage(10), Constraint::Percentage(90)])
+                .direction(Direction::Vertical)
+                .split(f.size());
+            let mut page_area = chunks[1];
+            let header = Layout::default()
+                .constraints([
+                    Constraint::Min(0),
+                    Constraint::Length(status.width() as u16),
+                ])
+                .direction(Direction::Horizontal)
+                .split(chunks[0]);
+            let lines = Layout::default()
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(header[0]);
+            let path = Paragraph::new(path);
+            let status = Paragraph::new(status).alignment(Alignment::Right);
+
+            f.render_widget(tabs, lines[0]);
+            f.render_widget(path, lines[1]);
+            crumb_areas = crumbs
+                .iter()
+                .map(|&(x, width)| Rect {
+                    x: lines[1].x + x,
+                    width,
+                    ..lines[1]
+                })
+                .collect();
+            f.render_widget(status, header[1]);
+
+            if command.is_some() || message.is_some() {
+                let chunks = Layout::default()
+                    .constraints([Constraint::Min(0), Constraint::Length(3)])
+                    .direction(Direction::Vertical)
+                    .split(page_area);
+                page_area = chunks[0];
+                match (command, message) {
+                    (Some(command), _) => command.render(f, chunks[1], "Command", true),
+                    (None, Some(message)) => f.render_widget(message, chunks[1]),
+                    (None, None) => {}
+                }
+            }
+            page.ui(f, page_area);
+
+            if let Some(finder) = finder {
+                finder.render(f, page_area);
+            }
+            if let Some(help) = &help {
+                render_help(help, page_area, f);
+            }
+        })?;
+
+        self.crumb_areas = crumb_areas;
+        Ok(())
+    }
+
+    /// Passes `event` to the current page and acts on its message. Returns false once the app
+    /// should quit.
+    pub fn handle_event(&mut self, event: Event) -> io::Result<bool> {
+        if self.editing_command {
+            return Ok(self.edit_command(event));
+        }
+        if self.crumb.is_some() {
+            self.pick_crumb(event);
+            return Ok(true);
+        }
+        if let Some(finder) = &mut self.finder {
+            match finder.handle_event(&event) {
+                Pick::Pending => {}
+                Pick::Cancelled => self.finder = None,
+                Pick::Picked(budget) => {
+                    self.finder = None;
+                    if let Err(e) = self.switch_budget(&budget.id.to_string()) {
+                        self.message = Some(e);
+                    }
+                }
+            }
+            return Ok(true);
+        }
+
+        if let Event::Mouse(mouse) = event {
+            if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+                if std::mem::take(&mut self.help) {
+                    return Ok(true);
+                }
+                let (column, row) = (mouse.column, mouse.row);
+                let crumb = self
+                    .crumb_areas
+                    .iter()
+                    .position(|a| contains(*a, column, row));
+                if let Some(depth) = crumb {
+                    self.tabs[self.tab].go_to(depth);
+                    return Ok(true);
+                }
+            }
+        }
+
+        let page = self.tabs[self.tab].page_mut();
+
+        if let Event::Key(key) = event {
+            if std::mem::take(&mut self.help) {
+                return Ok(true);
+            }
+            // Pages reading text get every key but the one that quits
+            let actions = match page.captures_input() {
+                true => &[Action::Quit],
+                false => Action::GLOBAL,
+            };
+            match keymap::get().action(&key, actions) {
+                Some(Action::Quit) => return Ok(false),
+                Some(Action::Help) => {
+                    self.help = true;
+                    return Ok(true);
+                }
+                Some(Action::Command) => {
+                    self.command.clear();
+                    self.editing_command = true;
+                    self.message = None;
+                    return Ok(true);
+                }
+                Some(Action::Back) => {
+                    self.tabs[self.tab].back();
+                    return Ok(true);
+                }
+                Some(Action::Forward) => {
+                    self.tabs[self.tab].forward();
+                    return Ok(true);
+                }
+                Some(Action::JumpBack) => {
+                    self.tabs[self.tab].jump_back();
+                    return Ok(true);
+                }
+                Some(Action::JumpForward) => {
+                    self.tabs[self.tab].jump_forward();
+                    return Ok(true);
+                }
+                Some(Action::Breadcrumbs) => {
+                    self.crumb = Some(self.tabs[self.tab].depth().saturating_sub(1));
+                    return Ok(true);
+                }
+                Some(Action::NewTab) => {
+                    self.new_tab();
+                    return Ok(true);
+                }
+                Some(Action::CloseTab) => {
+                    self.close_tab();
+                    return Ok(true);
+                }
+                Some(Action::NextTab) => {
+                    self.tab = (self.tab + 1) % self.tabs.len();
+                    return Ok(true);
+                }
+                Some(Action::PrevTab) => {
+                    self.tab = (self.tab + self.tabs.len() - 1) % self.tabs.len();
+                    return Ok(true);
+                }
+                Some(Action::FindBudget) => {
+                    if self.budgets.is_empty() {
+                        self.message = Some("The budgets have not been loaded yet".to_string());
+                    } else {
+                        let budgets = self.budgets.iter().map(|b| (b.name.clone(), b.clone()));
+                        self.finder = Some(FuzzyFinder::new("Budgets", budgets.collect()));
+                    }
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        let msg = page.update(event, &mut self.worker)?;
+        let running = self.apply(msg);
+
+        // Only now, so that a page that was just pushed gets the responses to its own requests
+        self.receive();
+        Ok(running)
+    }
+
+    /// Returns false if `msg` quits the app
+    fn apply(&mut self, msg: Message<B>) -> bool {
+        match msg {
+            Message::Quit => return false,
+            Message::Back => {
+                self.tabs[self.tab].back();
+            }
+            Message::Forward => {
+                self.tabs[self.tab].forward();
+            }
+            Message::NewPage(newpage) => self.tabs[self.tab].push(newpage),
+            Message::Noop => {}
+        }
+        true
+    }
+
+    /// Moves through the breadcrumbs with the left and right keys, or by number, and goes back
+    /// to the picked page
+    fn pick_crumb(&mut self, event: Event) {
+        let (Event::Key(key), Some(crumb)) = (event, self.crumb) else {
+            return;
+        };
+        let actions = &[
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::Select,
+            Action::Cancel,
+            Action::Breadcrumbs,
+        ];
+        let depth = self.tabs[self.tab].depth();
+        match (keymap::get().action(&key, actions), key.code) {
+            (Some(Action::MoveLeft), _) => self.crumb = Some(crumb.saturating_sub(1)),
+            (Some(Action::MoveRight), _) => self.crumb = Some((crumb + 1).min(depth)),
+            (Some(Action::Select), _) => {
+                self.tabs[self.tab].go_to(crumb);
+                self.crumb = None;
+            }
+            (Some(Action::Cancel | Action::Breadcrumbs), _) => self.crumb = None,
+            (_, KeyCode::Char(c)) => {
+                if let Some(n) = c
+                    .to_digit(10)
+                    .filter(|n| (1..=depth as u32 + 1).contains(n))
+                {
+                    self.tabs[self.tab].go_to(n as usize - 1);
+                    self.crumb = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn edit_command(&mut self, event: Event) -> bool {
+        if self.command.handle_event(&event) {
+            return true;
+        }
+        let Event::Key(key) = event else {
+            return true;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.editing_command = false,
+            KeyCode::Enter => {
+                self.editing_command = false;
+                self.command.commit();
+                let line = self.command.to_string();
+                return self.run_command(&line);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Runs a line typed after `:`. Returns false if it quits the app.
+    fn run_command(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+
+        match name {
+            "" => {}
+            "q" | "quit" => return false,
+            "b" | "budget" => {
+                if let Err(e) = self.switch_budget(args.trim()) {
+                    self.message = Some(e);
+                }
+            }
+            "tabnew" => {
+                self.new_tab();
+                if let Err(e) = self.switch_budget(args.trim()) {
+                    self.message = Some(e);
+                }
+            }
+            "tabclose" => self.close_tab(),
+            "export" => {
+                self.message = Some(self.export(args.trim()).unwrap_or_else(|e| e));
+            }
+            _ => {
+                let page = self.tabs[self.tab].page_mut();
+                match page.command(name, args.trim(), &mut self.worker) {
+                    Some(Ok(msg)) => return self.apply(msg),
+                    Some(Err(e)) => self.message = Some(e),
+                    None => self.message = Some(format!("Unknown command `{name}`")),
+                }
+            }
+        }
+        true
+    }
+
+    /// Opens a tab with the budget list after the current one
+    fn new_tab(&mut self) {
+        let homepage = Homepage::new(&mut self.worker);
+        self.tabs
+            .insert(self.tab + 1, History::new(Box::new(homepage)));
+        self.tab += 1;
+    }
+
+    /// Closes the current tab, unless it is the last one
+    fn close_tab(&mut self) {
+        if self.tabs.len() == 1 {
+            self.message = Some("This is the last tab, :quit to leave".to_string());
+            return;
+        }
+        self.tabs.remove(self.tab);
+        self.tab = self.tab.min(self.tabs.len() - 1);
+    }
+
+    /// Opens the budget with the given name or id, or goes back to the budget list if `name` is
+    /// empty. Either way starts a new trail, `jump_back` returns to the pages open before.
+    fn switch_budget(&mut self, name: &str) -> Result<(), String> {
+        if name.is_empty() {
+            self.tabs[self.tab].jump(vec![]);
+            return Ok(());
+        }
+
+        let budget = find_budget(&self.budgets, name).ok_or_else(|| {
+            if self.budgets.is_empty() {
+                return "The budgets have not been loaded yet".to_string();
+            }
+            let names: Vec<&str> = self.budgets.iter().map(|b| b.name.as_str()).collect();
+            format!("No budget `{name}`, expected one of {}", names.join(", "))
+        })?;
+
+        let page = AccountsPage::new(budget.clone(), &mut self.worker);
+        self.tabs[self.tab].jump(vec![Box::new(page)]);
+        Ok(())
+    }
+
+    /// `:export [format] file` writes the transactions of the current page. Without a format it
+    /// goes by the file extension. The file is everything after the format, spaces included.
+    fn export(&self, args: &str) -> Result<String, String> {
+        if args.is_empty() {
+            return Err("Usage: :export [format] file".to_string());
+        }
+        let parsed = args
+            .split_once(' ')
+            .map(|(format, path)| (format.parse::<Format>(), path));
+        let (format, path) = match parsed {
+            Some((Ok(format), path)) => (format, Path::new(path.trim())),
+            // No format, so the spaces belong to the path, unless the format was mistyped
+            parsed => {
+                let path = Path::new(args);
+                let format = match (Format::from_path(path), parsed) {
+                    (Some(format), _) => format,
+                    (None, Some((Err(e), _))) => return Err(e),
+                    (None, _) => {
+                        return Err(format!(
+                            "Pick a format for {args}: csv, jsonl, ledger or beancount"
+                        ))
+                    }
+                };
+                (format, path)
+            }
+        };
+
+        let transactions = self.tabs[self.tab]
+            .page()
+            .transactions()
+            .ok_or("There are no transactions to export here")?;
+
+        let mut file = io::BufWriter::new(
+            fs::File::create(path).map_err(|e| format!("{}: {e}", path.display()))?,
+        );
+        transactions
+            .write(format, &mut file)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+
+        Ok(format!(
+            "Exported {} transactions to {}",
+            transactions.transactions.len(),
+            path.display()
+        ))
+    }
+
+    /// Hands finished responses to the pages, without blocking. Returns whether any of them
+    /// changed what is on screen.
+    pub fn receive(&mut self) -> bool {
+        let status = self.worker.status();
+        let mut changed = false;
+        while let Some(response) = self.worker.try_recv() {
+            changed |= self.deliver(response);
+        }
+        changed || self.worker.status() != status
+    }
+
+    /// Blocks until every request in flight has been answered, so tests see a settled screen
+    pub fn settle(&mut self) {
+        while let Some(response) = self.worker.recv() {
+            self.deliver(response);
+        }
+    }
+
+    /// Returns whether a page changed
+    fn deliver(&mut self, response: Response) -> bool {
+        let mut changed = false;
+        for page in self.tabs.iter_mut().flat_map(History::pages_mut) {
+            changed |= page.on_response(&response);
+        }
+
+        if let (Request::Budgets, Ok(Payload::Budgets(budgets))) =
+            (&response.request, &response.payload)
+        {
+            self.budgets = budgets.clone();
+            self.open_default_budget();
+            changed = true;
+        }
+        changed
+    }
+
+    /// Opens `default_budget` the first time the budgets arrive, unless a budget is open already
+    fn open_default_budget(&mut self) {
+        if std::mem::replace(&mut self.opened_default_budget, true)
+            || self.tabs[self.tab].depth() > 0
+        {
+            return;
+        }
+        if let Some(name) = config::get().default_budget.clone() {
+            let _ = self.switch_budget(&name);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use clap::Parser;
+use ynab_tui::{data_layer::FixtureSource, mock_server::MockServer};
+
+/// Serves fixture budgets over a local imitation of the YNAB API.
+/// Point ynab_tui at it with `YNAB_BASE_URL=http://127.0.0.1:<port>/v1`.
+/// Built with the `mock` feature: `cargo run --features mock --bin mock_ynab`.
+#[derive(Parser)]
+#[command(about)]
+struct Args {
+    /// Directory of fixture JSON files, one per budget
+    #[arg(long, value_name = "DIR", default_value = "fixtures")]
+    fixtures: std::path::PathBuf,
+
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Answer with 429 after this many requests within an hour
+    #[arg(long, value_name = "REQUESTS")]
+    rate_limit: Option<usize>,
+
+    /// Reject requests that do not carry this bearer token
+    #[arg(long)]
+    token: Option<String>,
+}
+
+fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    let budgets = FixtureSource::load_budgets(&args.fixtures).map_err(std::io::Error::other)?;
+
+    let server = MockServer::start_on(&format!("127.0.0.1:{}", args.port), budgets)?;
+    if let Some(requests) = args.rate_limit {
+        server.set_rate_limit(requests);
+    }
+    if let Some(token) = &args.token {
+        server.set_token(token);
+    }
+
+    println!(
+        "Serving {} on {}",
+        args.fixtures.display(),
+        server.base_path()
+    );
+    server.join();
+    Ok(())
+}
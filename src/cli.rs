@@ -0,0 +1,517 @@
+//! Non-interactive subcommands that print budget data for scripts, e.g.
+//! `ynab_tui transactions --account Checking --since 2024-01-01 --format csv`
+use chrono::NaiveDate;
+use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+use ynab_openapi::models::{Account, BudgetSummary, TransactionDetail};
+
+use crate::{
+    components::{transaction_matches, Column},
+    config,
+    data_layer::{BudgetDataSource, DataError},
+    export::{self, Transactions},
+    import::{self, Duplicate, ImportRow},
+    util::{find_budget, plain_amount},
+};
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// List the budgets
+    Budgets {
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// List the open accounts of a budget
+    Accounts {
+        #[command(flatten)]
+        budget: BudgetArgs,
+        /// Include closed accounts
+        #[arg(long)]
+        all: bool,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// List transactions, newest first
+    Transactions {
+        #[command(flatten)]
+        selection: TransactionArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Export transactions for spreadsheets or plain-text accounting
+    Export {
+        #[command(flatten)]
+        selection: TransactionArgs,
+        #[arg(long, value_enum, default_value_t = export::Format::Csv)]
+        format: export::Format,
+        /// Write to this file instead of stdout
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Create the new transactions of a bank statement in an account
+    Import {
+        /// A CSV, OFX or QIF statement
+        file: PathBuf,
+        /// The layout of the statement, from `[banks]` in the config
+        #[arg(long)]
+        bank: Option<String>,
+        #[command(flatten)]
+        budget: BudgetArgs,
+        /// The account to import into, defaults to the bank's `account`
+        #[arg(long, short)]
+        account: Option<String>,
+        /// Only show which rows are new
+        #[arg(long)]
+        dry_run: bool,
+        /// Also create rows that look like transactions already in the account
+        #[arg(long)]
+        include_similar: bool,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// List categories, with what was budgeted and spent in a month
+    Categories {
+        #[command(flatten)]
+        budget: BudgetArgs,
+        /// e.g. 2024-05, defaults to the current month
+        #[arg(long)]
+        month: Option<String>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Show account balances and their total
+    Balance {
+        #[command(flatten)]
+        budget: BudgetArgs,
+        /// Only this account, by name or id
+        #[arg(long, short)]
+        account: Option<String>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct BudgetArgs {
+    /// Budget name or id, defaults to `default_budget` from the config
+    #[arg(long, short)]
+    budget: Option<String>,
+}
+
+/// Which transactions `transactions` and `export` print
+#[derive(Args, Debug, Clone)]
+pub struct TransactionArgs {
+    #[command(flatten)]
+    budget: BudgetArgs,
+    /// Only this account, by name or id
+    #[arg(long, short)]
+    account: Option<String>,
+    /// Only transactions on or after this date, e.g. 2024-01-01
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    since: Option<NaiveDate>,
+    /// Only transactions containing this text, like `/` in the transaction table
+    #[arg(long, short)]
+    filter: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct OutputArgs {
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Data(DataError),
+    Io(io::Error),
+    Csv(csv::Error),
+    /// The arguments do not match the budget, e.g. an unknown account
+    Usage(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Data(e) => write!(f, "{e}"),
+            CliError::Io(e) => write!(f, "{e}"),
+            CliError::Csv(e) => write!(f, "{e}"),
+            CliError::Usage(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<DataError> for CliError {
+    fn from(e: DataError) -> Self {
+        CliError::Data(e)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+impl From<csv::Error> for CliError {
+    fn from(e: csv::Error) -> Self {
+        CliError::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(e: serde_json::Error) -> Self {
+        CliError::Data(e.into())
+    }
+}
+
+/// Rows to print, and the data they came from for `--format json`
+struct Output {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    json: Value,
+}
+
+impl Output {
+    fn new<T: Serialize>(headers: &[&str], rows: Vec<Vec<String>>, json: &T) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows,
+            json: serde_json::to_value(json).unwrap_or_default(),
+        }
+    }
+
+    fn write(&self, format: Format, out: &mut impl io::Write) -> Result<(), CliError> {
+        match format {
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut *out, &self.json)?;
+                writeln!(out)?;
+            }
+            Format::Csv => {
+                let mut writer = csv::Writer::from_writer(out);
+                writer.write_record(&self.headers)?;
+                for row in &self.rows {
+                    writer.write_record(row)?;
+                }
+                writer.flush()?;
+            }
+            Format::Table => {
+                let mut widths: Vec<usize> =
+                    self.headers.iter().map(|h| h.chars().count()).collect();
+                for row in &self.rows {
+                    for (w, cell) in widths.iter_mut().zip(row) {
+                        *w = (*w).max(cell.chars().count());
+                    }
+                }
+                for row in std::iter::once(&self.headers).chain(&self.rows) {
+                    let cells: Vec<String> = row
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, w)| format!("{cell:w$}"))
+                        .collect();
+                    writeln!(out, "{}", cells.join("  ").trim_end())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs `command` against `source` and prints the result to `out`
+pub fn run(
+    command: &Command,
+    source: &mut impl BudgetDataSource,
+    out: &mut impl io::Write,
+) -> Result<(), CliError> {
+    let (output, format) = match command {
+        Command::Budgets { output } => {
+            let budgets = source.get_budgets()?;
+            let rows = budgets
+                .iter()
+                .map(|b| {
+                    vec![
+                        b.name.clone(),
+                        b.id.to_string(),
+                        b.last_modified_on.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            (
+                Output::new(&["Name", "Id", "Last modified"], rows, &budgets),
+                output.format,
+            )
+        }
+        Command::Accounts {
+            budget,
+            all,
+            output,
+        } => {
+            let budget = select_budget(source, budget)?;
+            let mut accounts = source.get_accounts(&budget.id.to_string())?;
+            accounts.retain(|a| !a.deleted && (*all || !a.closed));
+            let rows = accounts
+                .iter()
+                .map(|a| {
+                    vec![
+                        a.name.clone(),
+                        a.r#type.to_string(),
+                        plain_amount(a.balance),
+                        plain_amount(a.cleared_balance),
+                        plain_amount(a.uncleared_balance),
+                    ]
+                })
+                .collect();
+            let headers = ["Name", "Type", "Balance", "Cleared", "Uncleared"];
+            (Output::new(&headers, rows, &accounts), output.format)
+        }
+        Command::Transactions { selection, output } => {
+            let (_, transactions) = select_transactions(source, selection)?;
+            let columns = config::get().columns();
+            let headers: Vec<&str> = columns.iter().map(Column::title).collect();
+            let rows = transactions
+                .iter()
+                .map(|t| {
+                    columns
+                        .iter()
+                        .map(|c| match c {
+                            Column::Amount => plain_amount(t.amount),
+                            c => c.cell(t),
+                        })
+                        .collect()
+                })
+                .collect();
+            (Output::new(&headers, rows, &transactions), output.format)
+        }
+        Command::Export {
+            selection,
+            format,
+            output,
+        } => {
+            let (accounts, transactions) = select_transactions(source, selection)?;
+            let export = Transactions::new(&accounts, &transactions);
+            match output {
+                Some(path) => {
+                    let mut file = io::BufWriter::new(fs::File::create(path)?);
+                    export.write(*format, &mut file)?;
+                    file.flush()?;
+                }
+                None => export.write(*format, out)?,
+            }
+            return Ok(());
+        }
+        Command::Import {
+            file,
+            bank,
+            budget,
+            account,
+            dry_run,
+            include_similar,
+            output,
+        } => {
+            let bank = import::bank(bank.as_deref()).map_err(usage)?;
+            let statement = import::read(file, &bank).map_err(usage)?;
+
+            let budget_id = select_budget(source, budget)?.id.to_string();
+            let name = account.as_ref().or(bank.account.as_ref()).ok_or_else(|| {
+                CliError::Usage("Pick the account to import into with --account".to_string())
+            })?;
+            let account = source
+                .get_accounts(&budget_id)?
+                .into_iter()
+                .find(|a| a.id.to_string() == *name || a.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| CliError::Usage(format!("No account `{name}`")))?;
+
+            let existing =
+                source.get_transactions_by_account(&budget_id, &account.id.to_string())?;
+            let mut rows = import::preview(statement, &existing);
+            for row in &mut rows {
+                if *include_similar && row.duplicate != Some(Duplicate::Imported) {
+                    row.selected = true;
+                }
+            }
+            let transactions = import::save_transactions(&rows, account.id);
+            if !*dry_run && !transactions.is_empty() {
+                source.import_transactions(&budget_id, transactions)?;
+            }
+
+            let status = |row: &ImportRow| match &row.duplicate {
+                Some(Duplicate::Imported) => "imported before".to_string(),
+                _ if row.selected && !*dry_run => "created".to_string(),
+                None => "new".to_string(),
+                Some(Duplicate::Similar { date, payee }) => format!("like {date} {payee}"),
+            };
+            let table = rows
+                .iter()
+                .map(|r| {
+                    vec![
+                        r.row.date.to_string(),
+                        r.row.payee.clone(),
+                        r.row.memo.clone().unwrap_or_default(),
+                        plain_amount(r.row.amount),
+                        status(r),
+                    ]
+                })
+                .collect();
+            let json: Vec<Value> = rows
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "date": r.row.date,
+                        "payee": r.row.payee,
+                        "memo": r.row.memo,
+                        "amount": r.row.amount,
+                        "import_id": r.import_id,
+                        "status": status(r),
+                    })
+                })
+                .collect();
+            let headers = ["Date", "Payee", "Memo", "Amount", "Status"];
+            (Output::new(&headers, table, &json), output.format)
+        }
+        Command::Categories {
+            budget,
+            month,
+            output,
+        } => {
+            let budget = select_budget(source, budget)?;
+            let month = match month {
+                Some(month) if month.len() == 7 => format!("{month}-01"),
+                Some(month) => month.clone(),
+                None => "current".to_string(),
+            };
+            let mut categories = source.get_month(&budget.id.to_string(), &month)?.categories;
+            categories.retain(|c| !c.deleted && !c.hidden);
+            let rows = categories
+                .iter()
+                .map(|c| {
+                    vec![
+                        c.category_group_name.clone().unwrap_or_default(),
+                        c.name.clone(),
+                        plain_amount(c.budgeted),
+                        plain_amount(c.activity),
+                        plain_amount(c.balance),
+                    ]
+                })
+                .collect();
+            let headers = ["Group", "Category", "Budgeted", "Activity", "Balance"];
+            (Output::new(&headers, rows, &categories), output.format)
+        }
+        Command::Balance {
+            budget,
+            account,
+            output,
+        } => {
+            let budget = select_budget(source, budget)?;
+            let mut accounts = source.get_accounts(&budget.id.to_string())?;
+            accounts.retain(|a| !a.deleted && !a.closed);
+            if let Some(name) = account {
+                accounts.retain(|a| a.id.to_string() == *name || a.name.eq_ignore_ascii_case(name));
+                if accounts.is_empty() {
+                    return Err(CliError::Usage(format!("No account `{name}`")));
+                }
+            }
+
+            let total: i64 = accounts.iter().map(|a| a.balance).sum();
+            let mut rows: Vec<Vec<String>> = accounts
+                .iter()
+                .map(|a| vec![a.name.clone(), plain_amount(a.balance)])
+                .collect();
+            rows.push(vec!["Total".to_string(), plain_amount(total)]);
+
+            let json = serde_json::json!({
+                "accounts": accounts
+                    .iter()
+                    .map(|a| serde_json::json!({ "name": a.name, "id": a.id, "balance": a.balance }))
+                    .collect::<Vec<_>>(),
+                "total": total,
+            });
+            (
+                Output::new(&["Account", "Balance"], rows, &json),
+                output.format,
+            )
+        }
+    };
+
+    output.write(format, out)
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("`{date}` is not a date like 2024-01-31"))
+}
+
+fn usage(e: impl fmt::Display) -> CliError {
+    CliError::Usage(e.to_string())
+}
+
+/// The transactions matching `args`, and the accounts of their budget
+fn select_transactions(
+    source: &mut impl BudgetDataSource,
+    args: &TransactionArgs,
+) -> Result<(Vec<Account>, Vec<TransactionDetail>), CliError> {
+    let budget = select_budget(source, &args.budget)?;
+    let budget_id = budget.id.to_string();
+    let accounts = source.get_accounts(&budget_id)?;
+
+    let mut transactions = match &args.account {
+        Some(name) => {
+            let account = accounts
+                .iter()
+                .find(|a| a.id.to_string() == *name || a.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| CliError::Usage(format!("No account `{name}`")))?;
+            source.get_transactions_by_account(&budget_id, &account.id.to_string())?
+        }
+        None => source.get_transactions(&budget_id)?,
+    };
+    transactions.retain(|t| {
+        !t.deleted
+            && args
+                .since
+                .is_none_or(|since| t.date.parse::<NaiveDate>().is_ok_and(|d| d >= since))
+            && args
+                .filter
+                .as_ref()
+                .is_none_or(|f| transaction_matches(t, f))
+    });
+    Ok((accounts, transactions))
+}
+
+/// `--budget`, `default_budget` from the config, or the only budget there is
+fn select_budget(
+    source: &mut impl BudgetDataSource,
+    args: &BudgetArgs,
+) -> Result<BudgetSummary, CliError> {
+    let budgets = source.get_budgets()?;
+    let name = args
+        .budget
+        .as_deref()
+        .or(config::get().default_budget.as_deref());
+
+    match name {
+        Some(name) => find_budget(&budgets, name).cloned().ok_or_else(|| {
+            let names: Vec<&str> = budgets.iter().map(|b| b.name.as_str()).collect();
+            CliError::Usage(format!(
+                "No budget `{name}`, expected one of {}",
+                names.join(", ")
+            ))
+        }),
+        None if budgets.len() == 1 => Ok(budgets[0].clone()),
+        None => Err(CliError::Usage(
+            "There is more than one budget, pick one with --budget or `default_budget`".to_string(),
+        )),
+    }
+}
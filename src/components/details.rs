@@ -0,0 +1,117 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::*,
+    Frame,
+};
+use unicode_width::UnicodeWidthChar;
+use ynab_openapi::models::TransactionDetail;
+
+use super::{block, Column};
+use crate::{theme, util::milicent_to_dollars};
+
+/// A transaction as labelled values, one per line, with a line for each part of a split
+pub fn transaction_fields(t: &TransactionDetail) -> Vec<(&'static str, String)> {
+    let approved = if t.approved { "yes" } else { "no" };
+    let mut fields = vec![
+        ("Date", Column::Date.cell(t)),
+        ("Account", Column::Account.cell(t)),
+        ("Payee", Column::Payee.cell(t)),
+        ("Category", Column::Category.cell(t)),
+        ("Memo", Column::Memo.cell(t)),
+        ("Amount", Column::Amount.cell(t)),
+        ("Cleared", format!("{:?}", t.cleared)),
+        ("Approved", approved.to_string()),
+    ];
+    if let Some(flag) = t.flag_color {
+        fields.push(("Flag", format!("{flag:?}")));
+    }
+    if let (Some(account_id), Some(id)) = (t.transfer_account_id, &t.transfer_transaction_id) {
+        let payee = t.payee_name.as_deref().unwrap_or_default();
+        let account = match payee.strip_prefix("Transfer : ") {
+            Some(name) => name.to_string(),
+            None => account_id.to_string(),
+        };
+        fields.push(("Transfer", format!("{account}, transaction {id}")));
+    }
+    let optional = [
+        ("Matched", &t.matched_transaction_id),
+        ("Import ID", &t.import_id),
+        ("Import payee", &t.import_payee_name),
+        ("Bank payee", &t.import_payee_name_original),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            fields.push((name, value.clone()));
+        }
+    }
+    if let Some(debt) = t.debt_transaction_type {
+        fields.push(("Debt type", format!("{debt:?}")));
+    }
+    for s in &t.subtransactions {
+        let category = s.category_name.as_deref().unwrap_or("Uncategorized");
+        let amount = format!("${:.2}", milicent_to_dollars(s.amount));
+        let memo = s.memo.as_deref().unwrap_or_default();
+        let split = format!("{category}  {amount}  {memo}");
+        fields.push(("Split", split.trim_end().to_string()));
+    }
+    fields
+}
+
+/// Draws `fields` in a bordered box, the labels lined up in a column and long values wrapped
+/// beside them
+pub fn render_fields<B: Backend>(
+    frame: &mut Frame<B>,
+    area: Rect,
+    title: &str,
+    fields: &[(&str, String)],
+) {
+    let width = fields.iter().map(|(name, _)| name.len()).max();
+    let width = width.unwrap_or(0);
+    let value_width = (area.width as usize).saturating_sub(width + 4);
+    let label = theme::get().label;
+    let lines: Vec<Spans> = fields
+        .iter()
+        .flat_map(|(name, value)| {
+            wrap(value, value_width)
+                .into_iter()
+                .enumerate()
+                .map(move |(i, line)| {
+                    let name = if i == 0 { name } else { "" };
+                    Spans::from(vec![
+                        Span::styled(format!("{name:width$}  "), label),
+                        Span::raw(line),
+                    ])
+                })
+        })
+        .collect();
+    let paragraph = Paragraph::new(lines).block(block().title(title.to_string()));
+    frame.render_widget(paragraph, area);
+}
+
+/// Breaks `text` into lines at most `width` columns wide, between words where it can
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut line_width = 0;
+    for word in text.split_inclusive(' ') {
+        let word_width: usize = word.trim_end().chars().filter_map(|c| c.width()).sum();
+        if line_width > 0 && line_width + word_width.min(width) > width {
+            lines.push(String::new());
+            line_width = 0;
+        }
+        for c in word.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if line_width > 0 && line_width + c_width > width && c != ' ' {
+                lines.push(String::new());
+                line_width = 0;
+            }
+            lines.last_mut().unwrap().push(c);
+            line_width += c_width;
+        }
+    }
+    lines
+        .iter()
+        .map(|line| line.trim_end().to_string())
+        .collect()
+}
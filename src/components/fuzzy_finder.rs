@@ -0,0 +1,200 @@
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+use tui::{
+    backend::Backend,
+    layout::*,
+    text::{Span, Spans},
+    widgets::*,
+    Frame,
+};
+
+use super::{list_item_style, StatefulList, TextInput};
+use crate::{theme, util::centered_rect};
+
+/// What became of the finder after an event
+pub enum Pick<T> {
+    /// Still searching
+    Pending,
+    Picked(T),
+    Cancelled,
+}
+
+/// The position of an item in the finder and how well it matches the query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub index: usize,
+    pub score: i64,
+    /// The indices of the matched characters in the item's label
+    pub positions: Vec<usize>,
+}
+
+/// A popup that narrows a list down to the items whose labels fuzzily match what is typed, best
+/// matches first
+#[derive(Clone)]
+pub struct FuzzyFinder<T> {
+    query: TextInput,
+    /// Each item with the label it is searched and shown by
+    items: Vec<(String, T)>,
+    matches: StatefulList<Match>,
+}
+
+impl<T: Clone> FuzzyFinder<T> {
+    pub fn new(title: &str, items: Vec<(String, T)>) -> Self {
+        let mut matches = StatefulList::new();
+        matches.set_title(title).focus();
+        let mut finder = Self {
+            query: TextInput::new(),
+            items,
+            matches,
+        };
+        finder.search();
+        finder
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn matches(&self) -> &[Match] {
+        self.matches.items()
+    }
+
+    /// Up and down, or ctrl-p and ctrl-n, move through the matches, Enter picks one and Esc
+    /// closes the finder. Everything else edits the query.
+    pub fn handle_event(&mut self, event: &Event) -> Pick<T> {
+        if let Event::Key(key) = event {
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Pick::Cancelled,
+                KeyCode::Enter => {
+                    return match self.matches.get_selected() {
+                        Some(m) => Pick::Picked(self.items[m.index].1.clone()),
+                        None => Pick::Pending,
+                    }
+                }
+                KeyCode::Up | KeyCode::BackTab => {
+                    self.matches.select_prev();
+                    return Pick::Pending;
+                }
+                KeyCode::Char('p') if ctrl => {
+                    self.matches.select_prev();
+                    return Pick::Pending;
+                }
+                KeyCode::Down | KeyCode::Tab => {
+                    self.matches.select_next();
+                    return Pick::Pending;
+                }
+                KeyCode::Char('n') if ctrl => {
+                    self.matches.select_next();
+                    return Pick::Pending;
+                }
+                _ => {}
+            }
+        }
+
+        let query = self.query.to_string();
+        if self.query.handle_event(event) && *self.query != query {
+            self.search();
+        }
+        Pick::Pending
+    }
+
+    fn search(&mut self) {
+        let mut matches: Vec<Match> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (label, _))| {
+                let (score, positions) = fuzzy_match(&self.query, label)?;
+                Some(Match {
+                    index,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        // Stable, so equally good matches keep their order
+        matches.sort_by_key(|m| (-m.score, self.items[m.index].0.chars().count()));
+        self.matches.set_items(matches).unselect();
+        self.matches.select_next();
+    }
+
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let area = centered_rect(60, 60, area);
+        frame.render_widget(Clear, area);
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .direction(Direction::Vertical)
+            .split(area);
+
+        self.query.render(frame, chunks[0], "Find", true);
+        self.matches.render_with(frame, chunks[1], |m| {
+            highlighted(&self.items[m.index].0, &m.positions)
+        });
+    }
+}
+
+/// The label with the matched characters picked out
+fn highlighted<'a>(label: &str, positions: &[usize]) -> ListItem<'a> {
+    let matched = theme::get().matched;
+    let spans: Vec<Span> = label
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match positions.contains(&i) {
+            true => Span::styled(c.to_string(), matched),
+            false => Span::raw(c.to_string()),
+        })
+        .collect();
+    ListItem::new(Spans::from(spans)).style(list_item_style())
+}
+
+/// Whether the characters of `query` appear in `text` in order, ignoring case. Returns a score,
+/// higher for matches at word starts and in runs, and the indices of the matched characters.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.chars().collect();
+    if query.is_empty() {
+        return Some((0, vec![]));
+    }
+
+    // Trying each place the first character matches finds `cafe` at the word in `Corner Cafe`
+    // rather than spread over `Corner`
+    (0..text.len())
+        .filter(|&start| same(text[start], query[0]))
+        .filter_map(|start| {
+            let mut positions = vec![start];
+            let mut next = start + 1;
+            for &q in &query[1..] {
+                let i = (next..text.len()).find(|&i| same(text[i], q))?;
+                positions.push(i);
+                next = i + 1;
+            }
+            Some((score(&text, &positions), positions))
+        })
+        .max_by_key(|(score, positions)| (*score, -(positions[0] as i64)))
+}
+
+fn same(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn score(text: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    for (n, &i) in positions.iter().enumerate() {
+        score += 10;
+        let word_start = match i.checked_sub(1).map(|p| text[p]) {
+            None => true,
+            Some(prev) => {
+                !prev.is_alphanumeric() || (prev.is_lowercase() && text[i].is_uppercase())
+            }
+        };
+        if word_start {
+            score += 20;
+        }
+        match n.checked_sub(1).map(|p| positions[p]) {
+            Some(prev) if prev + 1 == i => score += 15,
+            Some(prev) => score -= (i - prev - 1) as i64,
+            None => score -= i.min(10) as i64,
+        }
+    }
+    score
+}
@@ -1,14 +1,13 @@
-use crate::util::*;
-use tui::{
-    backend::Backend,
-    layout::*,
-    style::{Color, Modifier, Style},
-    text::Spans,
-    widgets::*,
-    Frame,
+use crate::{
+    data_layer::DataError,
+    keymap::{self, Action},
+    theme,
+    util::*,
 };
+use tui::{backend::Backend, layout::*, style::Style, text::Spans, widgets::*, Frame};
 
 pub fn render_popup_message<B: Backend>(
+    title: &str,
     width: u16,
     height: u16,
     popup_area: Rect,
@@ -18,7 +17,7 @@ pub fn render_popup_message<B: Backend>(
 ) {
     let popup = Paragraph::new(message)
         .wrap(Wrap { trim: false })
-        .block(Block::default().borders(Borders::ALL).title("Error"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .alignment(alignment);
 
     let popup_area = centered_rect(width, height, popup_area);
@@ -26,29 +25,102 @@ pub fn render_popup_message<B: Backend>(
     frame.render_widget(popup, popup_area);
 }
 
+/// Lists the keys of `actions`, in two columns when one would not fit
+pub fn render_help<B: Backend>(actions: &[Action], area: Rect, frame: &mut Frame<B>) {
+    let keymap = keymap::get();
+    let rows = centered_rect(60, 90, area).height.saturating_sub(2);
+    if actions.len() <= rows as usize {
+        let help = keymap.help(actions);
+        return render_popup_message("Help", 60, 90, area, Alignment::Left, &help, frame);
+    }
+
+    let (left, right) = actions.split_at(actions.len().div_ceil(2));
+    let (left, right) = (keymap.help(left), keymap.help(right));
+    let left_width = left.lines().map(str::len).max().unwrap_or(0) as u16;
+
+    let popup_area = centered_rect(100, 90, area);
+    let popup = Block::default().borders(Borders::ALL).title("Help");
+    let columns = Layout::default()
+        .constraints([Constraint::Length(left_width + 3), Constraint::Min(0)])
+        .direction(Direction::Horizontal)
+        .split(popup.inner(popup_area));
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+    frame.render_widget(Paragraph::new(left), columns[0]);
+    frame.render_widget(Paragraph::new(right), columns[1]);
+}
+
+/// Shows why a request failed, and how to retry or dismiss it
+pub fn render_error_popup<B: Backend>(
+    error: &DataError,
+    stale: bool,
+    area: Rect,
+    frame: &mut Frame<B>,
+) {
+    let hint = match error {
+        DataError::Unauthorized => {
+            "\n\nUpdate the token, token_command or token_file in the config. \
+             Retrying reads it again."
+        }
+        DataError::Token(_) => {
+            "\n\ntoken_command can't ask for a passphrase while the app is open. Unlock it in \
+             another shell, e.g. by running it once, then retry."
+        }
+        _ => "",
+    };
+    let keys = keymap::get().hints(&[(Action::Refresh, "retry"), (Action::Cancel, "dismiss")]);
+    let message = if stale {
+        format!("Refreshing failed, showing cached data\n\n{error}{hint}\n\n{keys}")
+    } else {
+        format!("{error}{hint}\n\n{keys}")
+    };
+    render_popup_message("Error", 50, 30, area, Alignment::Center, &message, frame)
+}
+
+/// The current frame of a loading spinner, animated by wall clock time
+pub fn spinner() -> &'static str {
+    const FRAMES: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    FRAMES[(millis / 100) as usize % FRAMES.len()]
+}
+
+/// A widget title, with a spinner appended while its data is loading
+pub fn title(title: &str, loading: bool) -> String {
+    if loading {
+        format!("{title} {}", spinner())
+    } else {
+        title.to_string()
+    }
+}
+
 pub fn block() -> Block<'static> {
-    Block::default().borders(Borders::ALL)
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::get().border)
 }
 
 pub fn active_block() -> Block<'static> {
     Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().bg(Color::Black))
+        .border_style(theme::get().active_border)
 }
 
 pub fn list_item<'a>(item: String) -> ListItem<'a> {
     let lines = vec![Spans::from(item)];
 
-    ListItem::new(lines).style(Style::default().fg(Color::Black).bg(Color::White))
+    ListItem::new(lines).style(list_item_style())
+}
+
+pub fn list_item_style() -> Style {
+    theme::get().text
 }
 
 pub fn list<'a>(items: Vec<ListItem<'a>>, title: &'a str) -> List<'a> {
     List::new(items)
         .block(block().title(title))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
+        .highlight_style(theme::get().highlight)
         .highlight_symbol(">> ")
 }
@@ -7,5 +7,14 @@ pub use stateful_list::*;
 mod stateful_table;
 pub use stateful_table::*;
 
-mod command_pallete;
-pub use command_pallete::*;
+mod text_input;
+pub use text_input::*;
+
+mod fuzzy_finder;
+pub use fuzzy_finder::*;
+
+mod details;
+pub use details::*;
+
+mod mouse;
+pub use mouse::*;
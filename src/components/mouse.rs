@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+use tui::layout::Rect;
+
+/// How soon the second click has to follow the first to count as a double click
+pub const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+/// Tells a double click on a row from two clicks, on different rows or too far apart
+#[derive(Debug, Default, Clone)]
+pub struct DoubleClick {
+    last: Option<(usize, Instant)>,
+}
+
+impl DoubleClick {
+    /// Records a click on `row`, returns true if it completes a double click
+    pub fn click(&mut self, row: usize) -> bool {
+        let now = Instant::now();
+        match self.last.take() {
+            Some((last, at)) if last == row && now - at <= DOUBLE_CLICK => true,
+            _ => {
+                self.last = Some((row, now));
+                false
+            }
+        }
+    }
+}
+
+pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+    (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
+}
+
+/// The first row a list or table of one line rows shows once drawn. Like tui, it scrolls no
+/// further than it takes to show the selected row, and back to the top without a selection.
+pub(crate) fn scroll_offset(
+    offset: usize,
+    selected: Option<usize>,
+    len: usize,
+    height: usize,
+) -> usize {
+    let Some(selected) = selected.filter(|_| len > 0 && height > 0) else {
+        return 0;
+    };
+    let selected = selected.min(len - 1);
+    let offset = offset.min(len - 1);
+    if selected >= offset + height {
+        selected + 1 - height
+    } else {
+        offset.min(selected)
+    }
+}
@@ -1,16 +1,22 @@
-use tui::{backend::Backend, layout::Rect, style::*, terminal::Frame, widgets::*};
+use std::cell::{Cell, RefCell};
+use tui::{backend::Backend, layout::Rect, terminal::Frame, widgets::*};
 use ynab_openapi::models::{Account, BudgetSummary};
 
-use crate::util::force_mut_ref;
+use crate::theme;
 
-use super::helpers::*;
+use super::{contains, helpers::*, scroll_offset};
 
 #[derive(Clone)]
 pub struct StatefulList<T> {
-    state: ListState,
+    /// Borrowed mutably while drawing, where tui keeps its scroll offset in it
+    state: RefCell<ListState>,
     items: Vec<T>,
     active: bool,
+    loading: bool,
     title: String,
+    /// Where the list was last drawn and the first item it showed, to find clicked items
+    area: Cell<Rect>,
+    offset: Cell<usize>,
 }
 
 impl<T> Default for StatefulList<T> {
@@ -33,7 +39,10 @@ impl<T> StatefulList<T> {
             state: Default::default(),
             items: vec![],
             active: false,
+            loading: false,
             title: String::new(),
+            area: Cell::default(),
+            offset: Cell::default(),
         }
     }
 
@@ -57,8 +66,13 @@ impl<T> StatefulList<T> {
         self
     }
 
+    pub fn set_loading(&mut self, loading: bool) -> &mut Self {
+        self.loading = loading;
+        self
+    }
+
     pub fn get_selected(&self) -> Option<&T> {
-        let i = self.state.selected()?;
+        let i = self.state.borrow().selected()?;
         self.items.get(i)
     }
 
@@ -69,7 +83,7 @@ impl<T> StatefulList<T> {
             return None;
         }
 
-        let i = match self.state.selected() {
+        let i = match self.state.borrow().selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
                     0
@@ -79,7 +93,7 @@ impl<T> StatefulList<T> {
             }
             None => 0,
         };
-        self.state.select(Some(i));
+        self.state.get_mut().select(Some(i));
         Some(&self.items[i])
     }
 
@@ -90,7 +104,7 @@ impl<T> StatefulList<T> {
             return None;
         }
 
-        let i = match self.state.selected() {
+        let i = match self.state.borrow().selected() {
             Some(i) => {
                 if i == 0 {
                     self.items.len() - 1
@@ -100,60 +114,104 @@ impl<T> StatefulList<T> {
             }
             None => 0,
         };
-        self.state.select(Some(i));
+        self.state.get_mut().select(Some(i));
         Some(&self.items[i])
     }
 
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn select(&mut self, i: usize) -> Option<&T> {
+        let item = self.items.get(i)?;
+        self.state.get_mut().select(Some(i));
+        Some(item)
+    }
+
     pub fn unselect(&mut self) {
-        self.state.select(None);
+        self.state.get_mut().select(None);
+    }
+
+    /// Whether the list was last drawn over the cell
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        contains(self.area.get(), column, row)
+    }
+
+    /// The index of the item drawn at the cell, if any
+    pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = block().inner(self.area.get());
+        if !contains(inner, column, row) {
+            return None;
+        }
+        let i = self.offset.get() + (row - inner.y) as usize;
+        (i < self.items.len()).then_some(i)
+    }
+
+    /// Draws `list` with the selection, remembering where for `item_at`
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, list: List) {
+        let height = block().inner(area).height as usize;
+        let selected = self.state.borrow().selected();
+        let offset = scroll_offset(self.offset.get(), selected, self.items.len(), height);
+        self.area.set(area);
+        self.offset.set(offset);
+        f.render_stateful_widget(list, area, &mut self.state.borrow_mut())
+    }
+
+    /// Renders each item with `to_item`, for rows that need more than a plain string
+    pub fn render_with<'a, B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        to_item: impl Fn(&T) -> ListItem<'a>,
+    ) {
+        let items = self.items.iter().map(to_item).collect();
+        self.draw(f, area, self.list(items))
+    }
+
+    fn list<'a>(&self, items: Vec<ListItem<'a>>) -> List<'a> {
+        let title = title(&self.title, self.loading);
+        let block = if self.active {
+            active_block().title(title)
+        } else {
+            block().title(title)
+        };
+
+        List::new(items)
+            .block(block)
+            .highlight_style(theme::get().highlight)
+            .highlight_symbol(">> ")
     }
 }
+
 impl<T: Clone> StatefulList<T> {
-    fn ui<F>(&self, line_to_str: F) -> List
+    fn ui<F>(&self, line_to_str: F) -> List<'_>
     where
         F: Fn(T) -> String,
     {
-        let block = if self.active {
-            active_block().title(self.title.as_str())
-        } else {
-            block().title(self.title.as_str())
-        };
-
         let budget_items = self
             .items
             .iter()
             .map(|item| list_item(line_to_str(item.clone())))
             .collect::<Vec<_>>();
 
-        let budget_list = List::new(budget_items)
-            .block(block)
-            .highlight_style(
-                Style::default()
-                    .bg(Color::LightGreen)
-                    .add_modifier(Modifier::BOLD),
-            )
-            .highlight_symbol(">> ");
-        budget_list
+        self.list(budget_items)
     }
 }
 
 impl StatefulList<String> {
     pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
-        f.render_stateful_widget(self.ui(|b| b), area, unsafe { force_mut_ref(&self.state) })
+        self.draw(f, area, self.ui(|b| b))
     }
 }
 
 impl StatefulList<BudgetSummary> {
     pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
-        f.render_stateful_widget(self.ui(|b| b.name), area, unsafe {
-            force_mut_ref(&self.state)
-        })
+        self.draw(f, area, self.ui(|b| b.name))
     }
 }
 
 impl StatefulList<Account> {
     pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
-        let widget = self.ui(|a| a.name).to_owned();
-        f.render_stateful_widget(widget, area, unsafe { force_mut_ref(&self.state) })
+        self.draw(f, area, self.ui(|a| a.name))
     }
 }
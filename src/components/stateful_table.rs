@@ -1,15 +1,121 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::{cell, cmp::Ordering, collections::HashMap, ops::Range};
 use tui::{
     backend::Backend,
-    layout::{Constraint, Rect},
-    style::*,
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Span, Spans},
     widgets::*,
     Frame,
 };
-use ynab_openapi::models::TransactionDetail;
+use ynab_openapi::models::{transaction_detail::Cleared, TransactionDetail};
 
-use crate::util::{force_mut_ref, milicent_to_dollars};
+use crate::{theme, util::milicent_to_dollars};
 
-use super::{active_block, block};
+use super::{active_block, block, contains, scroll_offset, title};
+
+/// A column of the transaction table, as named in the config
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Date,
+    Payee,
+    Category,
+    Memo,
+    Amount,
+    Account,
+}
+
+impl Column {
+    pub const DEFAULT: &'static [Column] = &[
+        Column::Payee,
+        Column::Category,
+        Column::Memo,
+        Column::Amount,
+        Column::Date,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Date => "Date",
+            Column::Payee => "Payee",
+            Column::Category => "Category",
+            Column::Memo => "Memo",
+            Column::Amount => "Amount",
+            Column::Account => "Account",
+        }
+    }
+
+    /// Relative width, the columns shown share the table's width in proportion
+    fn weight(&self) -> u16 {
+        match self {
+            Column::Memo => 36,
+            Column::Payee => 24,
+            Column::Category | Column::Account => 20,
+            Column::Date | Column::Amount => 10,
+        }
+    }
+
+    /// The markers in front, then percentages that share the rest of the width between
+    /// `columns` in proportion to their weights
+    fn widths(columns: &[Column]) -> Vec<Constraint> {
+        let total: u16 = columns.iter().map(Column::weight).sum();
+        let columns = columns
+            .iter()
+            .map(|c| Constraint::Percentage(c.weight() * 94 / total.max(1)));
+        std::iter::once(MARKERS).chain(columns).collect()
+    }
+
+    /// Orders transactions by this column, amounts by value and text ignoring case
+    fn compare(&self, a: &TransactionDetail, b: &TransactionDetail) -> Ordering {
+        match self {
+            Column::Amount => a.amount.cmp(&b.amount),
+            _ => {
+                let (a, b) = (self.cell(a), self.cell(b));
+                a.to_lowercase().cmp(&b.to_lowercase())
+            }
+        }
+    }
+
+    pub fn cell(&self, t: &TransactionDetail) -> String {
+        match self {
+            Column::Date => t.date.clone(),
+            Column::Payee => t.payee_name.clone().unwrap_or_default(),
+            Column::Category => t.category_name.clone().unwrap_or_default(),
+            Column::Memo => t.memo.clone().unwrap_or_default(),
+            Column::Amount => format!("${:.2}", milicent_to_dollars(t.amount)),
+            Column::Account => t.account_name.clone(),
+        }
+    }
+}
+
+/// The headerless first column: flag, cleared status, transfer and match, one character each
+const MARKERS: Constraint = Constraint::Length(4);
+
+/// The flag, cleared, transfer and match markers of a transaction, blank where they don't apply
+fn markers(t: &TransactionDetail) -> Spans<'static> {
+    let theme = theme::get();
+    let flag = match t.flag_color {
+        Some(color) => Span::styled("⚑", theme.flag(color)),
+        None => Span::raw(" "),
+    };
+    let cleared = match t.cleared {
+        Cleared::Uncleared => Span::styled("○", theme.uncleared),
+        Cleared::Cleared => Span::raw("✓"),
+        Cleared::Reconciled => Span::raw("◆"),
+    };
+    let transfer = if t.transfer_account_id.is_some() {
+        "⇄"
+    } else {
+        " "
+    };
+    let matched = if t.matched_transaction_id.is_some() {
+        "≡"
+    } else {
+        " "
+    };
+    Spans::from(vec![flag, cleared, Span::raw(transfer), Span::raw(matched)])
+}
 
 #[derive(Clone)]
 pub struct StatefulTable<T> {
@@ -17,7 +123,17 @@ pub struct StatefulTable<T> {
     items: Vec<T>,
     filtered: Vec<T>,
     title: String,
+    columns: Vec<Column>,
+    widths: Vec<Constraint>,
     active: bool,
+    loading: bool,
+    /// The column the rows are sorted by, and whether in descending order
+    sort: Option<(Column, bool)>,
+    /// Where the table was last drawn and the first row it showed, to find clicked rows
+    area: cell::Cell<Rect>,
+    offset: cell::Cell<usize>,
+    /// Rows by index, formatted the first time they are shown and dropped when the items change
+    rows: cell::RefCell<HashMap<usize, Row<'static>>>,
 }
 
 #[rustfmt::skip]
@@ -30,15 +146,20 @@ impl<T> StatefulTable<T> {
             items: Vec::new(),
             filtered: Vec::new(),
             title: String::new(),
+            columns: Column::DEFAULT.to_vec(),
+            widths: Column::widths(Column::DEFAULT),
             active: false,
+            loading: false,
+            sort: None,
+            area: cell::Cell::default(),
+            offset: cell::Cell::default(),
+            rows: Default::default(),
         }
     }
 
-    pub fn set_items(&mut self, transactions: Vec<T>) -> &mut Self {
-        self.filtered.clear();
-        self.items = transactions;
-        self.unselect();
-        self
+    /// The items that pass the filter
+    pub fn items(&self) -> &[T] {
+        &self.items
     }
 
     pub fn set_title(&mut self, title: &str) -> &mut Self {
@@ -46,6 +167,18 @@ impl<T> StatefulTable<T> {
         self
     }
 
+    pub fn set_columns(&mut self, columns: &[Column]) -> &mut Self {
+        self.columns = columns.to_vec();
+        self.widths = Column::widths(columns);
+        self.rows.get_mut().clear();
+        self
+    }
+
+    pub fn set_loading(&mut self, loading: bool) -> &mut Self {
+        self.loading = loading;
+        self
+    }
+
     pub fn focus(&mut self) -> &mut Self {
         self.active = true;
         self
@@ -61,7 +194,13 @@ impl<T> StatefulTable<T> {
         self.items.get(i)
     }
 
-    pub fn select_next(&mut self) -> usize {
+    /// Returns the new selected index, or None if the table is empty, e.g. while it loads
+    pub fn select_next(&mut self) -> Option<usize> {
+        if self.items.is_empty() {
+            self.unselect();
+            return None;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -73,10 +212,16 @@ impl<T> StatefulTable<T> {
             None => 0,
         };
         self.state.select(Some(i));
-        i
+        Some(i)
     }
 
-    pub fn select_prev(&mut self) -> usize {
+    /// Returns the new selected index, or None if the table is empty
+    pub fn select_prev(&mut self) -> Option<usize> {
+        if self.items.is_empty() {
+            self.unselect();
+            return None;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -88,77 +233,254 @@ impl<T> StatefulTable<T> {
             None => 0,
         };
         self.state.select(Some(i));
-        i
+        Some(i)
     }
 
     pub fn unselect(&mut self) {
         self.state.select(None);
     }
 
-    fn ui<'a, F>(&'a self, to_cells: F) -> Table
-    where
-        F: Fn(&T) -> Vec<Cell<'a>>,
-    {
-        let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-        let table: Vec<Row> = self
-            .items
-            .iter()
-            .map(|items| Row::new(to_cells(items)))
+    /// How many rows the table showed when last drawn, at least one
+    fn page_height(&self) -> usize {
+        let inner = block().inner(self.area.get());
+        (inner.height as usize).saturating_sub(1).max(1)
+    }
+
+    /// Moves the selection down by a page, stopping at the last row
+    pub fn page_down(&mut self) {
+        let i = match self.state.selected() {
+            Some(i) => i + self.page_height(),
+            None => 0,
+        };
+        self.select(i.min(self.items.len().saturating_sub(1)));
+    }
+
+    /// Moves the selection up by a page, stopping at the first row
+    pub fn page_up(&mut self) {
+        let i = self.state.selected().unwrap_or(0);
+        self.select(i.saturating_sub(self.page_height()));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(self.items.len().saturating_sub(1));
+    }
+
+    pub fn select(&mut self, i: usize) -> Option<&T> {
+        let item = self.items.get(i)?;
+        self.state.select(Some(i));
+        Some(item)
+    }
+
+    /// Whether the table was last drawn over the cell
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        contains(self.area.get(), column, row)
+    }
+
+    /// The index of the row drawn at the cell, if any
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = block().inner(self.area.get());
+        // The first line is the header
+        if !contains(inner, column, row) || row == inner.y {
+            return None;
+        }
+        let i = self.offset.get() + (row - inner.y - 1) as usize;
+        (i < self.items.len()).then_some(i)
+    }
+
+    /// The column whose header is drawn at the cell, if any
+    pub fn header_at(&self, column: u16, row: u16) -> Option<Column> {
+        let inner = block().inner(self.area.get());
+        if !contains(inner, column, row) || row != inner.y {
+            return None;
+        }
+        let spans = self.column_spans(inner);
+        // The markers come first, without a header
+        let i = spans.iter().position(|span| span.contains(&column))?;
+        self.columns.get(i.checked_sub(1)?).copied()
+    }
+
+    /// The columns each header spans, laid out the way tui lays out the table: widths solved
+    /// at the left edge, then placed one after another with a space between
+    fn column_spans(&self, inner: Rect) -> Vec<Range<u16>> {
+        let selected = self.state.selected().is_some();
+        let mut constraints = vec![];
+        // tui makes room for the highlight symbol while a row is selected, the table has none
+        if selected {
+            constraints.push(Constraint::Length(0));
+        }
+        for width in &self.widths {
+            constraints.extend([*width, Constraint::Length(1)]);
+        }
+        constraints.pop();
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(Rect {
+                x: 0,
+                y: 0,
+                ..inner
+            });
+        let mut x = inner.x;
+        let widths = chunks.iter().skip(selected as usize).step_by(2);
+        widths
+            .map(|chunk| {
+                let span = x..x + chunk.width;
+                x += chunk.width + 1;
+                span
+            })
+            .collect()
+    }
+
+    /// Draws the rows that fit in `area`, only formatting the ones not in the cache with
+    /// `to_row`. Scrolls the way tui would had it been given every row.
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, to_row: impl Fn(&T) -> Row<'static>) {
+        // Less the borders and the header
+        let height = block().inner(area).height.saturating_sub(1) as usize;
+        let selected = self.state.selected();
+        let offset = scroll_offset(self.offset.get(), selected, self.items.len(), height);
+        self.area.set(area);
+        self.offset.set(offset);
+
+        let end = (offset + height).min(self.items.len());
+        let mut cache = self.rows.borrow_mut();
+        let rows = (offset..end)
+            .map(|i| {
+                let row = cache.entry(i).or_insert_with(|| to_row(&self.items[i]));
+                row.clone()
+            })
             .collect();
 
+        let mut state = TableState::default();
+        state.select(selected.and_then(|i| i.checked_sub(offset)));
+        f.render_stateful_widget(self.ui(rows), area, &mut state)
+    }
+
+    fn ui<'a>(&'a self, rows: Vec<Row<'a>>) -> Table<'a> {
+        let title = title(&self.title, self.loading);
         let block = if self.active {
-            active_block().title(self.title.as_str())
+            active_block().title(title)
         } else {
-            block().title(self.title.as_str())
+            block().title(title)
         };
 
-        let table = Table::new(table)
-            .header(Row::new(["Payee", "Category", "Memo", "Amount", "Date"]))
+        // In front, narrow columns cut their titles short
+        let headers = self.columns.iter().map(|c| match self.sort {
+            Some((sorted, descending)) if sorted == *c => {
+                format!("{}{}", if descending { "▼" } else { "▲" }, c.title())
+            }
+            _ => c.title().to_string(),
+        });
+        let headers = std::iter::once(String::new()).chain(headers);
+        let table = Table::new(rows)
+            .header(Row::new(headers))
             .block(block)
-            .highlight_style(selected_style)
-            .widths(&[
-                Constraint::Percentage(24),
-                Constraint::Percentage(20),
-                Constraint::Percentage(36),
-                Constraint::Percentage(10),
-                Constraint::Percentage(10),
-            ]);
+            .highlight_style(theme::get().selected_row)
+            .widths(&self.widths);
         table
     }
 }
 
 impl StatefulTable<TransactionDetail> {
     pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
-        let table = self.ui(|transaction| {
-            vec![
-                Cell::from(transaction.payee_name.clone().unwrap_or_default()),
-                Cell::from(transaction.category_name.clone().unwrap_or_default()),
-                Cell::from(transaction.memo.clone().unwrap_or_default()),
-                Cell::from(format!("${:.2}", milicent_to_dollars(transaction.amount))),
-                Cell::from(transaction.date.clone()),
-            ]
-        });
+        let theme = theme::get();
+        self.draw(f, area, |transaction| {
+            let cells = self.columns.iter().map(|c| {
+                let cell = Cell::from(c.cell(transaction));
+                match c {
+                    Column::Amount => cell.style(theme.amount(transaction.amount)),
+                    _ => cell,
+                }
+            });
+            let row = Row::new(std::iter::once(Cell::from(markers(transaction))).chain(cells));
+            match transaction.approved {
+                true => row,
+                false => row.style(theme.unapproved),
+            }
+        })
+    }
 
-        f.render_stateful_widget(table, area, unsafe { force_mut_ref(&self.state) })
+    /// Replaces the transactions. The selected one stays selected while it is still there, so
+    /// pages reloaded in the background keep their place.
+    pub fn set_items(&mut self, transactions: Vec<TransactionDetail>) -> &mut Self {
+        let selected = self.selected_id();
+        self.filtered.clear();
+        self.items = transactions;
+        self.rows.get_mut().clear();
+        self.select_id(selected);
+        self
     }
 
     pub fn filter(&mut self, filter: &str) {
+        let selected = self.selected_id();
         self.items.append(&mut self.filtered);
 
-        let filtered = self.items.drain_filter(|t| {
-            !format!(
-                "{}{}{}{}{}{}",
-                t.date,
-                t.amount,
-                t.memo.clone().unwrap_or_default(),
-                t.payee_name.clone().unwrap_or_default(),
-                t.category_name.clone().unwrap_or_default(),
-                t.account_name
-            )
-            .to_lowercase()
-            .contains(filter)
-        });
+        let filtered = self
+            .items
+            .extract_if(.., |t| !transaction_matches(t, filter));
         self.filtered.extend(filtered);
+        self.sort();
+        self.select_id(selected);
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.selected().map(|t| t.id.clone())
+    }
+
+    /// Selects the transaction with the id, nothing if it is not among the items
+    fn select_id(&mut self, id: Option<String>) {
+        let i = id.and_then(|id| self.items.iter().position(|t| t.id == id));
+        self.state.select(i);
+    }
+
+    /// Selects the transaction nearest to `date`, the first of them if there are several
+    pub fn select_date(&mut self, date: NaiveDate) -> Option<&TransactionDetail> {
+        let distance = |t: &TransactionDetail| match t.date.parse::<NaiveDate>() {
+            Ok(d) => (d - date).num_days().abs(),
+            Err(_) => i64::MAX,
+        };
+        let (i, _) = self
+            .items
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, t)| distance(t))?;
+        self.select(i)
+    }
+
+    /// Sorts the rows by `column`, or reverses them if they already are
+    pub fn sort_by(&mut self, column: Column) {
+        let descending = matches!(self.sort, Some((c, false)) if c == column);
+        self.sort = Some((column, descending));
+        self.sort();
         self.unselect();
     }
+
+    fn sort(&mut self) {
+        self.rows.get_mut().clear();
+        if let Some((column, descending)) = self.sort {
+            self.items.sort_by(|a, b| match descending {
+                false => column.compare(a, b),
+                true => column.compare(b, a),
+            });
+        }
+    }
+}
+
+/// Whether `filter` occurs in any of the transaction's fields, ignoring case
+pub fn transaction_matches(t: &TransactionDetail, filter: &str) -> bool {
+    format!(
+        "{}{}{}{}{}{}",
+        t.date,
+        t.amount,
+        t.memo.clone().unwrap_or_default(),
+        t.payee_name.clone().unwrap_or_default(),
+        t.category_name.clone().unwrap_or_default(),
+        t.account_name
+    )
+    .to_lowercase()
+    .contains(&filter.to_lowercase())
 }
@@ -0,0 +1,209 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Deref;
+use tui::{backend::Backend, layout::Rect, widgets::*, Frame};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::{active_block, block};
+
+/// A single line of editable text with a cursor, readline style motions and a history of the
+/// lines committed to it
+#[derive(Default, Clone)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset into `text`, always at a grapheme boundary
+    cursor: usize,
+    history: Vec<String>,
+    /// The history entry shown while browsing with up and down, and the line typed before
+    browsing: Option<(usize, String)>,
+}
+
+impl Deref for TextInput {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.text
+    }
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the text and moves the cursor to its end
+    pub fn set(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set("");
+        self.browsing = None;
+    }
+
+    /// Adds the text to the history, unless it is empty or repeats the last entry
+    pub fn commit(&mut self) {
+        self.browsing = None;
+        if !self.text.is_empty() && self.history.last() != Some(&self.text) {
+            self.history.push(self.text.clone());
+        }
+    }
+
+    /// Edits the text with a key or a paste. Returns false for events it does not handle, like
+    /// Enter and Esc, which the owner acts on.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            // Pasted newlines would submit a single line input halfway
+            Event::Paste(text) => {
+                self.insert(&text.replace(['\r', '\n'], " "));
+                true
+            }
+            Event::Key(key) => self.handle_key(key),
+            _ => false,
+        }
+    }
+
+    fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Char('b') if alt => self.cursor = self.word_start(),
+            KeyCode::Char('f') if alt => self.cursor = self.word_end(),
+            KeyCode::Char('w') if ctrl => self.delete_to(self.word_start()),
+            KeyCode::Char('u') if ctrl => self.delete_to(0),
+            KeyCode::Char('k') if ctrl => self.delete_to(self.text.len()),
+            KeyCode::Char('d') if ctrl => self.delete_to(self.next_boundary()),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Backspace if ctrl || alt => self.delete_to(self.word_start()),
+            KeyCode::Backspace => self.delete_to(self.prev_boundary()),
+            KeyCode::Delete => self.delete_to(self.next_boundary()),
+            KeyCode::Left if ctrl || alt => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.prev_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Up => self.browse_back(),
+            KeyCode::Down => self.browse_forward(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn insert(&mut self, s: &str) {
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    /// Deletes the text between the cursor and `to`, on either side of it
+    fn delete_to(&mut self, to: usize) {
+        let range = self.cursor.min(to)..self.cursor.max(to);
+        self.cursor = range.start;
+        self.text.replace_range(range, "");
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        let next = self.text[self.cursor..].graphemes(true).next();
+        self.cursor + next.map_or(0, str::len)
+    }
+
+    /// The start of the word before the cursor, skipping the spaces and punctuation in between
+    fn word_start(&self) -> usize {
+        let mut start = self.cursor;
+        let mut in_word = false;
+        for (i, g) in self.text[..self.cursor].grapheme_indices(true).rev() {
+            if is_word(g) {
+                in_word = true;
+            } else if in_word {
+                break;
+            }
+            start = i;
+        }
+        start
+    }
+
+    /// The end of the word after the cursor, skipping the spaces and punctuation in between
+    fn word_end(&self) -> usize {
+        let mut end = self.cursor;
+        let mut in_word = false;
+        for g in self.text[self.cursor..].graphemes(true) {
+            if is_word(g) {
+                in_word = true;
+            } else if in_word {
+                break;
+            }
+            end += g.len();
+        }
+        end
+    }
+
+    fn browse_back(&mut self) {
+        let index = match &self.browsing {
+            Some((0, _)) => return,
+            Some((i, _)) => i - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        let draft = match self.browsing.take() {
+            Some((_, draft)) => draft,
+            None => self.text.clone(),
+        };
+        self.set(&self.history[index].clone());
+        self.browsing = Some((index, draft));
+    }
+
+    fn browse_forward(&mut self) {
+        let Some((index, draft)) = self.browsing.take() else {
+            return;
+        };
+        match self.history.get(index + 1).cloned() {
+            Some(entry) => {
+                self.set(&entry);
+                self.browsing = Some((index + 1, draft));
+            }
+            None => self.set(&draft),
+        }
+    }
+
+    /// The cursor's offset in terminal columns, wide characters count twice
+    pub fn cursor_width(&self) -> usize {
+        self.text[..self.cursor].width()
+    }
+
+    /// Draws the input in a bordered box, scrolled so the cursor stays visible. The terminal
+    /// cursor is only placed while `focused`.
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, title: &str, focused: bool) {
+        let block = if focused {
+            active_block().title(title)
+        } else {
+            block().title(title)
+        };
+        let inner = block.inner(area);
+        let cursor = self.cursor_width() as u16;
+        let scroll = cursor.saturating_sub(inner.width.saturating_sub(1));
+
+        let paragraph = Paragraph::new(self.text.as_str())
+            .block(block)
+            .scroll((0, scroll));
+        frame.render_widget(paragraph, area);
+
+        if focused && inner.width > 0 && inner.height > 0 {
+            frame.set_cursor(inner.x + cursor - scroll, inner.y);
+        }
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().any(|c| c.is_alphanumeric() || c == '_')
+}
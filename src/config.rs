@@ -0,0 +1,629 @@
+use chrono::Duration;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use crate::{
+    components::Column,
+    data_layer::Resource,
+    import::{ColumnRef, StatementFormat},
+    keymap::Keymap,
+    theme::Theme,
+};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Everything read from `config.toml`. Environment variables override the file, `.env` only
+/// fills in what is left unset.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The personal access token. Prefer `token_command` or `token_file` over keeping it here.
+    pub token: Option<Secret>,
+    /// A shell command that prints the token, e.g. `pass ynab`
+    pub token_command: Option<String>,
+    /// A file containing only the token, readable by nobody but its owner
+    pub token_file: Option<PathBuf>,
+    pub cache_file: Option<PathBuf>,
+    /// Where the pane sizes of each page are saved
+    pub layout_file: Option<PathBuf>,
+    /// Defaults to `https://api.ynab.com/v1`
+    pub base_url: Option<String>,
+    pub refresh: Refresh,
+    /// Name or id of the budget to select on startup
+    pub default_budget: Option<String>,
+    /// `dark`, `light` or one of `themes`
+    pub theme: Option<String>,
+    /// Themes by name, each a `base` theme to start from and the styles it changes, e.g.
+    /// `outflow = "bold red"`
+    pub themes: BTreeMap<String, HashMap<String, String>>,
+    /// Click to select, double click to open, scroll with the wheel. Off by default, as it takes
+    /// over the terminal's own text selection.
+    pub mouse: bool,
+    /// Action name to the keys bound to it, e.g. `move_down = ["j", "down"]`
+    pub keybindings: HashMap<String, Keys>,
+    /// Columns of the transaction table, in order
+    pub columns: Option<Vec<Column>>,
+    pub export: Export,
+    /// How to read each bank's statements for `:import`, by a name of your choosing
+    pub banks: BTreeMap<String, Bank>,
+    /// Used when neither `--profile` nor `YNAB_PROFILE` pick one
+    pub default_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+    /// The name of the profile in use, if any
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// A YNAB login, its settings take precedence over the top level ones
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub token: Option<Secret>,
+    pub token_command: Option<String>,
+    pub token_file: Option<PathBuf>,
+    pub cache_file: Option<PathBuf>,
+    pub base_url: Option<String>,
+    pub default_budget: Option<String>,
+}
+
+/// How long cached responses count as fresh, e.g. `"90s"`, `"15m"`, `"1h"` or `"1d"`.
+/// Anything not set falls back to `default`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Refresh {
+    #[serde(deserialize_with = "duration")]
+    pub default: Duration,
+    #[serde(deserialize_with = "optional_duration")]
+    pub budgets: Option<Duration>,
+    #[serde(deserialize_with = "optional_duration")]
+    pub accounts: Option<Duration>,
+    #[serde(deserialize_with = "optional_duration")]
+    pub transactions: Option<Duration>,
+    #[serde(deserialize_with = "optional_duration")]
+    pub categories: Option<Duration>,
+    #[serde(deserialize_with = "optional_duration")]
+    pub payees: Option<Duration>,
+    #[serde(deserialize_with = "optional_duration")]
+    pub months: Option<Duration>,
+}
+
+impl Default for Refresh {
+    fn default() -> Self {
+        Self {
+            default: Duration::hours(1),
+            budgets: None,
+            accounts: None,
+            transactions: None,
+            categories: None,
+            payees: None,
+            months: None,
+        }
+    }
+}
+
+impl Refresh {
+    /// The durations that differ from `default`
+    pub fn overrides(&self) -> Vec<(Resource, Duration)> {
+        [
+            (Resource::Budgets, self.budgets),
+            (Resource::Accounts, self.accounts),
+            (Resource::Transactions, self.transactions),
+            (Resource::Categories, self.categories),
+            (Resource::Payees, self.payees),
+            (Resource::Months, self.months),
+        ]
+        .into_iter()
+        .filter_map(|(resource, duration)| Some((resource, duration?)))
+        .collect()
+    }
+}
+
+/// How exports lay out CSV and name ledger accounts
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Export {
+    /// Columns of CSV exports, defaults to `columns`
+    pub columns: Option<Vec<Column>>,
+    /// The commodity of ledger and beancount amounts
+    pub commodity: String,
+    /// YNAB account name to ledger account, e.g. `Checking = "Assets:Bank:Checking"`
+    pub accounts: HashMap<String, String>,
+    /// YNAB category name to ledger account, e.g. `Groceries = "Expenses:Food:Groceries"`
+    pub categories: HashMap<String, String>,
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        Self {
+            columns: None,
+            commodity: "USD".to_string(),
+            accounts: HashMap::new(),
+            categories: HashMap::new(),
+        }
+    }
+}
+
+/// The layout of a bank's statements. Columns are header names or indices counted from 0, and
+/// are guessed from the header when unset.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bank {
+    /// Defaults to the file extension
+    pub format: Option<StatementFormat>,
+    /// Name of the account to import into when none is selected
+    pub account: Option<String>,
+    pub delimiter: char,
+    /// Lines before the header, e.g. the account number and period
+    pub skip_rows: usize,
+    /// Whether the first row names the columns
+    pub headers: bool,
+    pub date: Option<ColumnRef>,
+    /// A chrono format such as `%d/%m/%Y`, common ones are tried when unset
+    pub date_format: Option<String>,
+    /// Signed amounts. Some banks use separate `inflow` and `outflow` columns instead.
+    pub amount: Option<ColumnRef>,
+    pub inflow: Option<ColumnRef>,
+    pub outflow: Option<ColumnRef>,
+    pub payee: Option<ColumnRef>,
+    pub memo: Option<ColumnRef>,
+    /// For statements that show spending as positive amounts
+    pub negate: bool,
+    /// For amounts written like `1.234,56`
+    pub decimal_comma: bool,
+}
+
+impl Default for Bank {
+    fn default() -> Self {
+        Self {
+            format: None,
+            account: None,
+            delimiter: ',',
+            skip_rows: 0,
+            headers: true,
+            date: None,
+            date_format: None,
+            amount: None,
+            inflow: None,
+            outflow: None,
+            payee: None,
+            memo: None,
+            negate: false,
+            decimal_comma: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// Neither the config nor the environment provide a token
+    MissingToken,
+    TokenCommand(String),
+    TokenFile(PathBuf, String),
+    NoCacheDir,
+    /// The profile and the ones that are configured
+    UnknownProfile(String, Vec<String>),
+    Keybindings(String),
+    Theme(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "Could not read {}: {e}", path.display()),
+            ConfigError::Parse(path, e) => write!(f, "Invalid config in {}: {e}", path.display()),
+            ConfigError::MissingToken => write!(
+                f,
+                "No YNAB token configured. Set `token_command` or `token_file` in {}, \
+                 or YNAB_TOKEN in the environment",
+                default_path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or("config.toml".to_string())
+            ),
+            ConfigError::TokenCommand(e) => write!(f, "token_command failed: {e}"),
+            ConfigError::TokenFile(path, e) => write!(f, "token_file {}: {e}", path.display()),
+            ConfigError::UnknownProfile(name, known) if known.is_empty() => {
+                write!(f, "Unknown profile `{name}`, no profiles are configured")
+            }
+            ConfigError::UnknownProfile(name, known) => write!(
+                f,
+                "Unknown profile `{name}`, expected one of {}",
+                known.join(", ")
+            ),
+            ConfigError::Keybindings(e) => write!(f, "{e}"),
+            ConfigError::Theme(e) => write!(f, "{e}"),
+            ConfigError::NoCacheDir => write!(
+                f,
+                "Could not find a cache directory, set `cache_file` or YNAB_CACHE_FILE"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads `path`, or the default location if `None`. Only an explicitly given file has to exist.
+    /// `profile` overrides `YNAB_PROFILE` and `default_profile`.
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let dotenv = dotenvy::dotenv_iter()
+            .map(|vars| vars.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+        Self::load_with_dotenv(path, profile, &dotenv)
+    }
+
+    /// `load`, with `dotenv` in place of the variables in the `.env` file
+    pub fn load_with_dotenv(
+        path: Option<&Path>,
+        profile: Option<&str>,
+        dotenv: &HashMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let env = |name: &str| std::env::var(name).ok();
+        let dotenv = |name: &str| dotenv.get(name).cloned();
+
+        let env_path = env("YNAB_CONFIG")
+            .or(dotenv("YNAB_CONFIG"))
+            .map(PathBuf::from);
+        let (path, required) = match path.map(Path::to_path_buf).or(env_path) {
+            Some(path) => (Some(path), true),
+            None => (default_path(), false),
+        };
+
+        let mut config = match path {
+            Some(path) => match fs::read_to_string(&path) {
+                Ok(s) => toml::from_str(&s).map_err(|e| ConfigError::Parse(path, e))?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Config::default(),
+                Err(e) => return Err(ConfigError::Read(path, e)),
+            },
+            None => Config::default(),
+        };
+
+        let profile = profile
+            .map(str::to_string)
+            .or(env("YNAB_PROFILE"))
+            .or(config.default_profile.clone())
+            .or(dotenv("YNAB_PROFILE"));
+        if let Some(profile) = profile {
+            config.use_profile(&profile)?;
+        }
+        config.apply_env(env);
+        config.fill_from(dotenv);
+        Keymap::new(&config.keybindings).map_err(ConfigError::Keybindings)?;
+        Theme::new(config.theme.as_deref(), &config.themes).map_err(ConfigError::Theme)?;
+        Ok(config)
+    }
+
+    /// Replaces the top level settings with the ones the profile sets
+    pub fn use_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let known = self.profiles.keys().cloned().collect();
+            return Err(ConfigError::UnknownProfile(name.to_string(), known));
+        };
+
+        // A token source in the profile replaces all of the top level ones
+        if profile.token.is_some()
+            || profile.token_command.is_some()
+            || profile.token_file.is_some()
+        {
+            self.token = profile.token;
+            self.token_command = profile.token_command;
+            self.token_file = profile.token_file;
+        }
+        if profile.cache_file.is_some() {
+            self.cache_file = profile.cache_file;
+        }
+        if profile.base_url.is_some() {
+            self.base_url = profile.base_url;
+        }
+        if profile.default_budget.is_some() {
+            self.default_budget = profile.default_budget;
+        }
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The `YNAB_*` settings that `var` has
+    fn env_settings(var: impl Fn(&str) -> Option<String>) -> Profile {
+        Profile {
+            token: var("YNAB_TOKEN").map(Secret::new),
+            token_command: var("YNAB_TOKEN_COMMAND"),
+            token_file: var("YNAB_TOKEN_FILE").map(PathBuf::from),
+            cache_file: var("YNAB_CACHE_FILE").map(PathBuf::from),
+            base_url: var("YNAB_BASE_URL"),
+            default_budget: var("YNAB_DEFAULT_BUDGET"),
+        }
+    }
+
+    /// Replaces the settings `var` has, for the environment
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        let env = Self::env_settings(&var);
+        // Like a profile's, a token source here replaces all of the configured ones
+        if env.token.is_some() || env.token_command.is_some() || env.token_file.is_some() {
+            self.token = env.token;
+            self.token_command = env.token_command;
+            self.token_file = env.token_file;
+        }
+        if env.cache_file.is_some() {
+            self.cache_file = env.cache_file;
+        }
+        if env.base_url.is_some() {
+            self.base_url = env.base_url;
+        }
+        if env.default_budget.is_some() {
+            self.default_budget = env.default_budget;
+        }
+        if let Some(theme) = var("YNAB_THEME") {
+            self.theme = Some(theme);
+        }
+    }
+
+    /// Fills in the settings that are still unset from `var`, for `.env`
+    fn fill_from(&mut self, var: impl Fn(&str) -> Option<String>) {
+        let dotenv = Self::env_settings(&var);
+        // A plaintext token must not win over a configured `token_command` or `token_file`
+        if self.token.is_none() && self.token_command.is_none() && self.token_file.is_none() {
+            self.token = dotenv.token;
+            self.token_command = dotenv.token_command;
+            self.token_file = dotenv.token_file;
+        }
+        self.cache_file = self.cache_file.take().or(dotenv.cache_file);
+        self.base_url = self.base_url.take().or(dotenv.base_url);
+        self.default_budget = self.default_budget.take().or(dotenv.default_budget);
+        self.theme = self.theme.take().or(var("YNAB_THEME"));
+    }
+
+    /// `token`, the contents of `token_file`, or the output of `token_command`, in that order.
+    /// For before the UI starts, `token_command` can still ask for a passphrase on the terminal.
+    pub fn token(&self) -> Result<Secret, ConfigError> {
+        self.read_token(Stdio::inherit)
+    }
+
+    /// `token` while the UI holds the terminal. `token_command` gets no input, so one that would
+    /// prompt fails instead of competing with the UI for the keys.
+    pub fn refresh_token(&self) -> Result<Secret, ConfigError> {
+        self.read_token(Stdio::null)
+    }
+
+    fn read_token(&self, stdin: fn() -> Stdio) -> Result<Secret, ConfigError> {
+        if let Some(token) = &self.token {
+            return Ok(token.clone());
+        }
+        if let Some(path) = &self.token_file {
+            return read_token_file(path);
+        }
+        if let Some(command) = &self.token_command {
+            return run_token_command(command, stdin());
+        }
+        Err(ConfigError::MissingToken)
+    }
+
+    /// `cache_file`, or `cache.json` in the user's cache directory, one per profile
+    pub fn cache_file(&self) -> Result<PathBuf, ConfigError> {
+        if let Some(cache_file) = &self.cache_file {
+            return Ok(cache_file.clone());
+        }
+        let dir = dirs::cache_dir()
+            .ok_or(ConfigError::NoCacheDir)?
+            .join("ynab_tui");
+        fs::create_dir_all(&dir).map_err(|e| ConfigError::Read(dir.clone(), e))?;
+        Ok(match &self.profile {
+            Some(profile) => dir.join(format!("cache-{profile}.json")),
+            None => dir.join("cache.json"),
+        })
+    }
+
+    /// `layout_file`, or `layouts.json` in the user's data directory. `None` if there is none,
+    /// layouts are then only kept until the app quits.
+    pub fn layout_file(&self) -> Option<PathBuf> {
+        if let Some(layout_file) = &self.layout_file {
+            return Some(layout_file.clone());
+        }
+        let dir = dirs::data_local_dir()?.join("ynab_tui");
+        fs::create_dir_all(&dir).ok()?;
+        Some(dir.join("layouts.json"))
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.columns.as_deref().unwrap_or(Column::DEFAULT)
+    }
+}
+
+fn run_token_command(command: &str, stdin: Stdio) -> Result<Secret, ConfigError> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(stdin)
+        .output()
+        .map_err(|e| ConfigError::TokenCommand(e.to_string()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ConfigError::TokenCommand(format!(
+            "`{command}` exited with {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() {
+        return Err(ConfigError::TokenCommand(format!(
+            "`{command}` printed nothing"
+        )));
+    }
+    Ok(Secret::new(token))
+}
+
+fn read_token_file(path: &Path) -> Result<Secret, ConfigError> {
+    let error = |e: String| ConfigError::TokenFile(path.to_path_buf(), e);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .map_err(|e| error(e.to_string()))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            return Err(error(format!(
+                "is accessible by other users (mode {:o}), run `chmod 600 {}`",
+                mode & 0o777,
+                path.display()
+            )));
+        }
+    }
+
+    let token = fs::read_to_string(path)
+        .map_err(|e| error(e.to_string()))?
+        .trim()
+        .to_string();
+    if token.is_empty() {
+        return Err(error("is empty".to_string()));
+    }
+    Ok(Secret::new(token))
+}
+
+/// `~/.config/ynab_tui/config.toml` on Linux, the platform equivalent elsewhere
+pub fn default_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("ynab_tui").join("config.toml"))
+}
+
+/// Makes `config` available to the pages through `config::get`. Only the first call has an
+/// effect.
+pub fn set(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// The config passed to `set`, or the defaults if there was none, e.g. in tests
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// A value that must not show up in logs or error messages, its `Debug` is redacted
+#[derive(Deserialize, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: String) -> Self {
+        Self(secret)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(***)")
+    }
+}
+
+/// One or more keys, written like `"j"`, `"ctrl-c"`, `"enter"` or `["k", "up"]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keys(pub Vec<KeyEvent>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        let keys = match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(key) => vec![key],
+            OneOrMany::Many(keys) => keys,
+        };
+        keys.iter()
+            .map(|k| parse_key(k).map_err(serde::de::Error::custom))
+            .collect::<Result<_, _>>()
+            .map(Keys)
+    }
+}
+
+pub fn parse_key(s: &str) -> Result<KeyEvent, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = s;
+    loop {
+        let lower = rest.to_lowercase();
+        let (modifier, len) = if lower.starts_with("ctrl-") {
+            (KeyModifiers::CONTROL, 5)
+        } else if lower.starts_with("alt-") {
+            (KeyModifiers::ALT, 4)
+        } else if lower.starts_with("shift-") {
+            (KeyModifiers::SHIFT, 6)
+        } else {
+            break;
+        };
+        modifiers |= modifier;
+        rest = &rest[len..];
+    }
+
+    let code = match rest.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+            KeyCode::F(f[1..].parse().unwrap())
+        }
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return Err(format!("unknown key `{s}`")),
+            }
+        }
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// Parses `"90s"`, `"15m"`, `"1h"` or `"1d"`
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let error = || format!("invalid duration `{s}`, expected a number followed by s, m, h or d");
+    let s = s.trim();
+    let unit = s.chars().last().ok_or_else(error)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()]
+        .trim()
+        .parse()
+        .map_err(|_| error())?;
+    match unit {
+        's' => Ok(Duration::seconds(n)),
+        'm' => Ok(Duration::minutes(n)),
+        'h' => Ok(Duration::hours(n)),
+        'd' => Ok(Duration::days(n)),
+        _ => Err(error()),
+    }
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_duration(&s).map_err(serde::de::Error::custom)
+}
+
+fn optional_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    duration(deserializer).map(Some)
+}
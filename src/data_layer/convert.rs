@@ -0,0 +1,41 @@
+//! The generated bindings declare a separate `Cleared` and `FlagColor` enum for every model,
+//! these convert between the ones of `TransactionDetail` and `SaveTransaction`.
+use ynab_openapi::models::{save_transaction, transaction_detail};
+
+pub fn cleared_to_save(cleared: transaction_detail::Cleared) -> save_transaction::Cleared {
+    match cleared {
+        transaction_detail::Cleared::Cleared => save_transaction::Cleared::Cleared,
+        transaction_detail::Cleared::Uncleared => save_transaction::Cleared::Uncleared,
+        transaction_detail::Cleared::Reconciled => save_transaction::Cleared::Reconciled,
+    }
+}
+
+pub fn cleared_to_detail(cleared: save_transaction::Cleared) -> transaction_detail::Cleared {
+    match cleared {
+        save_transaction::Cleared::Cleared => transaction_detail::Cleared::Cleared,
+        save_transaction::Cleared::Uncleared => transaction_detail::Cleared::Uncleared,
+        save_transaction::Cleared::Reconciled => transaction_detail::Cleared::Reconciled,
+    }
+}
+
+pub fn flag_to_save(flag: transaction_detail::FlagColor) -> save_transaction::FlagColor {
+    match flag {
+        transaction_detail::FlagColor::Red => save_transaction::FlagColor::Red,
+        transaction_detail::FlagColor::Orange => save_transaction::FlagColor::Orange,
+        transaction_detail::FlagColor::Yellow => save_transaction::FlagColor::Yellow,
+        transaction_detail::FlagColor::Green => save_transaction::FlagColor::Green,
+        transaction_detail::FlagColor::Blue => save_transaction::FlagColor::Blue,
+        transaction_detail::FlagColor::Purple => save_transaction::FlagColor::Purple,
+    }
+}
+
+pub fn flag_to_detail(flag: save_transaction::FlagColor) -> transaction_detail::FlagColor {
+    match flag {
+        save_transaction::FlagColor::Red => transaction_detail::FlagColor::Red,
+        save_transaction::FlagColor::Orange => transaction_detail::FlagColor::Orange,
+        save_transaction::FlagColor::Yellow => transaction_detail::FlagColor::Yellow,
+        save_transaction::FlagColor::Green => transaction_detail::FlagColor::Green,
+        save_transaction::FlagColor::Blue => transaction_detail::FlagColor::Blue,
+        save_transaction::FlagColor::Purple => transaction_detail::FlagColor::Purple,
+    }
+}
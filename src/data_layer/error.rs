@@ -0,0 +1,81 @@
+use std::fmt;
+use ynab_openapi::{apis, models::ErrorResponse};
+
+/// Everything that can go wrong while fetching data, in a form that can be shown to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataError {
+    /// YNAB rejected the access token
+    Unauthorized,
+    /// Too many requests were made within the last hour
+    RateLimited,
+    /// YNAB responded with an error status
+    Api {
+        status: u16,
+        detail: String,
+    },
+    /// The request never got a response, e.g. no network connection
+    Network(String),
+    /// Offline and nothing has been cached for the request yet
+    Offline,
+    /// A response or the cache could not be (de)serialized
+    Serde(String),
+    Io(String),
+    /// Something the app looked for is missing from what YNAB returned
+    NotFound(String),
+    /// A new token was needed after YNAB rejected the old one, and getting it failed
+    Token(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Unauthorized => {
+                write!(f, "YNAB rejected the access token, check that it is valid")
+            }
+            DataError::RateLimited => {
+                write!(f, "YNAB rate limit reached, try again in a few minutes")
+            }
+            DataError::Api { status, detail } => write!(f, "YNAB error {status}: {detail}"),
+            DataError::Network(e) => write!(f, "Could not reach YNAB: {e}"),
+            DataError::Offline => write!(f, "Offline, and this has not been cached yet"),
+            DataError::Serde(e) => write!(f, "Could not read response: {e}"),
+            DataError::Io(e) => write!(f, "IO error: {e}"),
+            DataError::NotFound(what) => write!(f, "{what} not found"),
+            DataError::Token(e) => write!(f, "Could not get a new token: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl<T> From<apis::Error<T>> for DataError {
+    fn from(e: apis::Error<T>) -> Self {
+        match e {
+            apis::Error::Reqwest(e) => DataError::Network(e.to_string()),
+            apis::Error::Serde(e) => DataError::Serde(e.to_string()),
+            apis::Error::Io(e) => DataError::Io(e.to_string()),
+            apis::Error::ResponseError(response) => match response.status.as_u16() {
+                401 => DataError::Unauthorized,
+                429 => DataError::RateLimited,
+                status => DataError::Api {
+                    status,
+                    detail: serde_json::from_str::<ErrorResponse>(&response.content)
+                        .map(|r| r.error.detail)
+                        .unwrap_or(response.content),
+                },
+            },
+        }
+    }
+}
+
+impl From<serde_json::Error> for DataError {
+    fn from(e: serde_json::Error) -> Self {
+        DataError::Serde(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for DataError {
+    fn from(e: std::io::Error) -> Self {
+        DataError::Io(e.to_string())
+    }
+}
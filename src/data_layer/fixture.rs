@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use ynab_openapi::models::{
+    Account, BudgetSummary, CategoryGroupWithCategories, MonthDetail, MonthSummary, Payee,
+    SaveTransaction, TransactionDetail,
+};
+
+use super::{
+    convert::{cleared_to_detail, flag_to_detail},
+    ApiResult, BudgetDataSource, DataError, Edit,
+};
+
+/// A budget and everything in it, as stored in a fixture file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FixtureBudget {
+    pub budget: BudgetSummary,
+    #[serde(default)]
+    pub accounts: Vec<Account>,
+    #[serde(default)]
+    pub transactions: Vec<TransactionDetail>,
+    #[serde(default)]
+    pub category_groups: Vec<CategoryGroupWithCategories>,
+    #[serde(default)]
+    pub payees: Vec<Payee>,
+    #[serde(default)]
+    pub months: Vec<MonthDetail>,
+}
+
+/// An in-memory `BudgetDataSource`, loaded from a directory with one JSON file per budget.
+/// Edits only change the in-memory copy.
+#[derive(Debug, Default)]
+pub struct FixtureSource {
+    budgets: Vec<FixtureBudget>,
+    created: usize,
+}
+
+impl FixtureSource {
+    pub fn new(budgets: Vec<FixtureBudget>) -> Self {
+        Self {
+            budgets,
+            created: 0,
+        }
+    }
+
+    pub fn load(dir: impl AsRef<Path>) -> ApiResult<Self> {
+        Ok(Self::new(Self::load_budgets(dir)?))
+    }
+
+    /// Reads every `*.json` file in `dir`, in file name order
+    pub fn load_budgets(dir: impl AsRef<Path>) -> ApiResult<Vec<FixtureBudget>> {
+        let mut files = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        files.retain(|f| f.extension().is_some_and(|ext| ext == "json"));
+        files.sort();
+
+        files
+            .iter()
+            .map(|f| {
+                let reader = io::BufReader::new(fs::File::open(f)?);
+                serde_json::from_reader(reader)
+                    .map_err(|e| DataError::Serde(format!("{}: {e}", f.display())))
+            })
+            .collect()
+    }
+
+    fn budget(&mut self, budget_id: &str) -> ApiResult<&mut FixtureBudget> {
+        self.budgets
+            .iter_mut()
+            .find(|b| b.budget.id.to_string() == budget_id)
+            .ok_or_else(|| DataError::Api {
+                status: 404,
+                detail: "Budget not found".to_string(),
+            })
+    }
+}
+
+impl BudgetDataSource for FixtureSource {
+    fn get_budgets(&mut self) -> ApiResult<Vec<BudgetSummary>> {
+        Ok(self.budgets.iter().map(|b| b.budget.clone()).collect())
+    }
+
+    fn get_accounts(&mut self, budget_id: &str) -> ApiResult<Vec<Account>> {
+        Ok(self.budget(budget_id)?.accounts.clone())
+    }
+
+    fn get_transactions(&mut self, budget_id: &str) -> ApiResult<Vec<TransactionDetail>> {
+        let mut ts = self.budget(budget_id)?.transactions.clone();
+        ts.sort_by(|a, b| b.date.cmp(&a.date));
+        Ok(ts)
+    }
+
+    fn get_transactions_by_account(
+        &mut self,
+        budget_id: &str,
+        account_id: &str,
+    ) -> ApiResult<Vec<TransactionDetail>> {
+        let mut ts = self.get_transactions(budget_id)?;
+        ts.retain(|t| t.account_id.to_string() == account_id);
+        Ok(ts)
+    }
+
+    fn get_categories(&mut self, budget_id: &str) -> ApiResult<Vec<CategoryGroupWithCategories>> {
+        Ok(self.budget(budget_id)?.category_groups.clone())
+    }
+
+    fn get_payees(&mut self, budget_id: &str) -> ApiResult<Vec<Payee>> {
+        Ok(self.budget(budget_id)?.payees.clone())
+    }
+
+    fn get_months(&mut self, budget_id: &str) -> ApiResult<Vec<MonthSummary>> {
+        let months = &self.budget(budget_id)?.months;
+        Ok(months
+            .iter()
+            .map(|m| MonthSummary {
+                month: m.month.clone(),
+                note: m.note.clone(),
+                income: m.income,
+                budgeted: m.budgeted,
+                activity: m.activity,
+                to_be_budgeted: m.to_be_budgeted,
+                age_of_money: m.age_of_money,
+                deleted: m.deleted,
+            })
+            .collect())
+    }
+
+    fn get_month(&mut self, budget_id: &str, month: &str) -> ApiResult<MonthDetail> {
+        let months = &self.budget(budget_id)?.months;
+        let found = if month == "current" {
+            months.iter().max_by(|a, b| a.month.cmp(&b.month))
+        } else {
+            months.iter().find(|m| m.month.get(..7) == month.get(..7))
+        };
+        found.cloned().ok_or_else(|| DataError::Api {
+            status: 404,
+            detail: "Month not found".to_string(),
+        })
+    }
+
+    fn save(&mut self, edit: &Edit) -> ApiResult<()> {
+        self.created += 1;
+        let id = format!("fixture-{}", self.created);
+        let budget = self.budget(edit.budget_id())?;
+
+        match edit {
+            Edit::Create { transaction, .. } => {
+                let mut t = TransactionDetail {
+                    id,
+                    ..Default::default()
+                };
+                apply(&mut t, transaction, budget);
+                budget.transactions.push(t);
+            }
+            Edit::Update {
+                original,
+                transaction,
+                ..
+            } => {
+                let mut ts = std::mem::take(&mut budget.transactions);
+                for t in ts.iter_mut().filter(|t| t.id == original.id) {
+                    apply(t, transaction, budget);
+                }
+                budget.transactions = ts;
+            }
+            Edit::Delete { original, .. } => budget.transactions.retain(|t| t.id != original.id),
+            Edit::Import { transactions, .. } => {
+                for (i, save) in transactions.iter().enumerate() {
+                    let seen = budget.transactions.iter().any(|t| {
+                        t.import_id.is_some() && t.import_id.as_ref() == save.import_id.as_ref()
+                    });
+                    if seen {
+                        continue;
+                    }
+                    let mut t = TransactionDetail {
+                        id: format!("{id}-{i}"),
+                        ..Default::default()
+                    };
+                    apply(&mut t, save, budget);
+                    budget.transactions.push(t);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Copies the fields set in `s` onto `t`, resolving names the way YNAB would
+pub(crate) fn apply(t: &mut TransactionDetail, s: &SaveTransaction, budget: &FixtureBudget) {
+    if let Some(account_id) = s.account_id {
+        t.account_id = account_id;
+        if let Some(account) = budget.accounts.iter().find(|a| a.id == account_id) {
+            t.account_name = account.name.clone();
+        }
+    }
+    if let Some(date) = &s.date {
+        t.date = date.clone();
+    }
+    if let Some(amount) = s.amount {
+        t.amount = amount;
+    }
+    if let Some(payee_id) = s.payee_id {
+        t.payee_id = Some(payee_id);
+        t.payee_name = budget
+            .payees
+            .iter()
+            .find(|p| p.id == payee_id)
+            .map(|p| p.name.clone());
+    } else if let Some(payee_name) = &s.payee_name {
+        t.payee_id = None;
+        t.payee_name = Some(payee_name.clone());
+    }
+    if let Some(category_id) = s.category_id {
+        t.category_id = Some(category_id);
+        t.category_name = budget
+            .category_groups
+            .iter()
+            .flat_map(|g| g.categories.iter())
+            .find(|c| c.id == category_id)
+            .map(|c| c.name.clone());
+    }
+    if s.memo.is_some() {
+        t.memo = s.memo.clone();
+    }
+    if let Some(cleared) = s.cleared {
+        t.cleared = cleared_to_detail(cleared);
+    }
+    if let Some(approved) = s.approved {
+        t.approved = approved;
+    }
+    if let Some(flag_color) = s.flag_color {
+        t.flag_color = Some(flag_to_detail(flag_color));
+    }
+    if s.import_id.is_some() {
+        t.import_id = s.import_id.clone();
+    }
+}
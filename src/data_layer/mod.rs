@@ -1,2 +1,19 @@
+mod error;
+pub use error::*;
+
 mod ynab_api;
 pub use ynab_api::*;
+
+mod source;
+pub use source::*;
+
+pub mod convert;
+
+mod fixture;
+pub use fixture::*;
+
+mod outbox;
+pub use outbox::*;
+
+mod worker;
+pub use worker::*;
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fs, io};
+use ynab_openapi::models::{SaveTransaction, TransactionDetail};
+
+use super::ApiResult;
+
+/// A change to a budget, queued in the outbox while offline
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Edit {
+    Create {
+        budget_id: String,
+        transaction: SaveTransaction,
+    },
+    /// `original` is the transaction as it was when edited, used to detect conflicting changes
+    Update {
+        budget_id: String,
+        original: TransactionDetail,
+        transaction: SaveTransaction,
+    },
+    Delete {
+        budget_id: String,
+        original: TransactionDetail,
+    },
+    /// Transactions from a bank statement, created in one request. YNAB skips the ones whose
+    /// `import_id` it has seen before.
+    Import {
+        budget_id: String,
+        transactions: Vec<SaveTransaction>,
+    },
+}
+
+impl Edit {
+    pub fn budget_id(&self) -> &str {
+        match self {
+            Edit::Create { budget_id, .. }
+            | Edit::Update { budget_id, .. }
+            | Edit::Delete { budget_id, .. }
+            | Edit::Import { budget_id, .. } => budget_id,
+        }
+    }
+}
+
+/// An edit that could not be replayed because the transaction changed on the server in the meantime
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Conflict {
+    pub edit: Edit,
+    /// The server's version of the transaction, `None` if it no longer exists
+    pub server: Option<TransactionDetail>,
+}
+
+/// Edits waiting for connectivity, persisted next to the cache so they survive restarts
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Outbox {
+    pending: VecDeque<Edit>,
+    conflicts: Vec<Conflict>,
+    #[serde(skip)]
+    file: String,
+}
+
+impl Outbox {
+    pub fn load(file: &str) -> Self {
+        let mut outbox: Outbox = fs::File::open(file)
+            .map(|f| serde_json::from_reader(io::BufReader::new(f)).unwrap_or_default())
+            .unwrap_or_default();
+        outbox.file = file.to_string();
+        outbox
+    }
+
+    pub fn push(&mut self, edit: Edit) -> ApiResult<()> {
+        self.pending.push_back(edit);
+        self.save()
+    }
+
+    pub fn front(&self) -> Option<&Edit> {
+        self.pending.front()
+    }
+
+    /// Removes the oldest edit after it was sent
+    pub fn pop(&mut self) -> ApiResult<()> {
+        self.pending.pop_front();
+        self.save()
+    }
+
+    /// Moves the oldest edit aside so it no longer blocks the ones queued after it
+    pub fn conflict(&mut self, server: Option<TransactionDetail>) -> ApiResult<()> {
+        if let Some(edit) = self.pending.pop_front() {
+            self.conflicts.push(Conflict { edit, server });
+        }
+        self.save()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    fn save(&self) -> ApiResult<()> {
+        let w = io::BufWriter::new(fs::File::create(&self.file)?);
+        serde_json::to_writer(w, self)?;
+        Ok(())
+    }
+}
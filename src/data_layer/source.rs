@@ -0,0 +1,115 @@
+use ynab_openapi::models::{
+    Account, BudgetSummary, CategoryGroupWithCategories, MonthDetail, MonthSummary, Payee,
+    SaveTransaction, TransactionDetail,
+};
+
+use super::{ApiResult, DataError, Edit};
+
+/// Connectivity and outbox state, for the status indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Status {
+    pub offline: bool,
+    pub pending: usize,
+    pub conflicts: usize,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if self.offline {
+            parts.push("offline".to_string());
+        }
+        if self.pending > 0 {
+            parts.push(format!("{} pending", self.pending));
+        }
+        if self.conflicts > 0 {
+            parts.push(format!("{} conflicts", self.conflicts));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Everything the pages read from or write to a budget. Implemented by `YnabApi` for the real
+/// thing, and by `FixtureSource` for demos and tests.
+pub trait BudgetDataSource: Send {
+    fn get_budgets(&mut self) -> ApiResult<Vec<BudgetSummary>>;
+
+    fn get_accounts(&mut self, budget_id: &str) -> ApiResult<Vec<Account>>;
+
+    /// Every transaction in the budget, newest first
+    fn get_transactions(&mut self, budget_id: &str) -> ApiResult<Vec<TransactionDetail>>;
+
+    /// The transactions of a single account, newest first
+    fn get_transactions_by_account(
+        &mut self,
+        budget_id: &str,
+        account_id: &str,
+    ) -> ApiResult<Vec<TransactionDetail>>;
+
+    fn get_categories(&mut self, budget_id: &str) -> ApiResult<Vec<CategoryGroupWithCategories>>;
+
+    fn get_payees(&mut self, budget_id: &str) -> ApiResult<Vec<Payee>>;
+
+    fn get_months(&mut self, budget_id: &str) -> ApiResult<Vec<MonthSummary>>;
+
+    /// `month` is an ISO date within the month, e.g. `2024-05-01`, or `current`
+    fn get_month(&mut self, budget_id: &str, month: &str) -> ApiResult<MonthDetail>;
+
+    fn save(&mut self, edit: &Edit) -> ApiResult<()>;
+
+    /// The error that caused the last response to be served from stale data, if any
+    fn take_stale(&mut self) -> Option<DataError> {
+        None
+    }
+
+    fn status(&self) -> Status {
+        Status::default()
+    }
+
+    fn create_transaction(
+        &mut self,
+        budget_id: &str,
+        transaction: SaveTransaction,
+    ) -> ApiResult<()> {
+        self.save(&Edit::Create {
+            budget_id: budget_id.to_string(),
+            transaction,
+        })
+    }
+
+    /// Creates the transactions in one request, skipping any whose `import_id` was imported before
+    fn import_transactions(
+        &mut self,
+        budget_id: &str,
+        transactions: Vec<SaveTransaction>,
+    ) -> ApiResult<()> {
+        self.save(&Edit::Import {
+            budget_id: budget_id.to_string(),
+            transactions,
+        })
+    }
+
+    fn update_transaction(
+        &mut self,
+        budget_id: &str,
+        original: &TransactionDetail,
+        transaction: SaveTransaction,
+    ) -> ApiResult<()> {
+        self.save(&Edit::Update {
+            budget_id: budget_id.to_string(),
+            original: original.clone(),
+            transaction,
+        })
+    }
+
+    fn delete_transaction(
+        &mut self,
+        budget_id: &str,
+        original: &TransactionDetail,
+    ) -> ApiResult<()> {
+        self.save(&Edit::Delete {
+            budget_id: budget_id.to_string(),
+            original: original.clone(),
+        })
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+use ynab_openapi::models::{
+    Account, BudgetSummary, CategoryGroupWithCategories, Payee, TransactionDetail,
+};
+
+use super::{BudgetDataSource, DataError, Edit, Status};
+use crate::event::AppEvent;
+
+/// A fetch that a page wants performed off of the UI thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Budgets,
+    Accounts {
+        budget_id: String,
+    },
+    Transactions {
+        budget_id: String,
+    },
+    TransactionsByAccount {
+        budget_id: String,
+        account_id: String,
+    },
+    Payees {
+        budget_id: String,
+    },
+    Categories {
+        budget_id: String,
+    },
+    Save(Box<Edit>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Payload {
+    Budgets(Vec<BudgetSummary>),
+    Accounts(Vec<Account>),
+    Transactions(Vec<TransactionDetail>),
+    Payees(Vec<Payee>),
+    Categories(Vec<CategoryGroupWithCategories>),
+    /// The edit was sent, or queued in the outbox while offline
+    Saved,
+}
+
+/// The result of a `Request`, handed back to the event loop
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub request: Request,
+    pub payload: Result<Payload, DataError>,
+    /// Set when the payload came from an expired cache entry because refreshing it failed
+    pub stale: Option<DataError>,
+    pub status: Status,
+}
+
+/// Owns the data source on a background thread so that slow network calls never block rendering.
+pub struct Worker {
+    requests: Option<Sender<Request>>,
+    responses: Receiver<Response>,
+    handle: Option<JoinHandle<()>>,
+    in_flight: usize,
+    status: Status,
+    /// Where to send `AppEvent::Response` once a response is ready, see `notify`
+    notify: Arc<Mutex<Option<Sender<AppEvent>>>>,
+}
+
+impl Worker {
+    pub fn spawn<S: BudgetDataSource + 'static>(mut source: S) -> Self {
+        let status = source.status();
+        let (request_tx, request_rx) = mpsc::channel::<Request>();
+        let (response_tx, response_rx) = mpsc::channel();
+        let notify = Arc::new(Mutex::new(None::<Sender<AppEvent>>));
+
+        let wake = notify.clone();
+        let handle = thread::spawn(move || {
+            for request in request_rx {
+                let payload = fetch(&mut source, &request);
+                let response = Response {
+                    request,
+                    payload,
+                    stale: source.take_stale(),
+                    status: source.status(),
+                };
+                if response_tx.send(response).is_err() {
+                    break;
+                }
+                if let Some(events) = wake.lock().unwrap().as_ref() {
+                    let _ = events.send(AppEvent::Response);
+                }
+            }
+        });
+
+        Self {
+            requests: Some(request_tx),
+            responses: response_rx,
+            handle: Some(handle),
+            in_flight: 0,
+            status,
+            notify,
+        }
+    }
+
+    /// Wakes the event loop through `events` whenever a response is ready
+    pub fn notify(&self, events: Sender<AppEvent>) {
+        *self.notify.lock().unwrap() = Some(events);
+    }
+
+    pub fn request(&mut self, request: Request) {
+        if let Some(requests) = &self.requests {
+            if requests.send(request).is_ok() {
+                self.in_flight += 1;
+            }
+        }
+    }
+
+    /// Returns a finished response if there is one, without blocking
+    pub fn try_recv(&mut self) -> Option<Response> {
+        let response = self.responses.try_recv().ok()?;
+        self.in_flight -= 1;
+        self.status = response.status;
+        Some(response)
+    }
+
+    /// Blocks until a response is finished, `None` if nothing is in flight
+    pub fn recv(&mut self) -> Option<Response> {
+        if !self.is_busy() {
+            return None;
+        }
+        let response = self.responses.recv().ok()?;
+        self.in_flight -= 1;
+        self.status = response.status;
+        Some(response)
+    }
+
+    /// Connectivity and outbox state as of the last response
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.in_flight > 0
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // Closing the channel ends the worker loop, which drops the source so it can flush its cache
+        self.requests.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn fetch(source: &mut impl BudgetDataSource, request: &Request) -> Result<Payload, DataError> {
+    Ok(match request {
+        Request::Budgets => Payload::Budgets(source.get_budgets()?),
+        Request::Accounts { budget_id } => Payload::Accounts(source.get_accounts(budget_id)?),
+        Request::Transactions { budget_id } => {
+            Payload::Transactions(source.get_transactions(budget_id)?)
+        }
+        Request::TransactionsByAccount {
+            budget_id,
+            account_id,
+        } => Payload::Transactions(source.get_transactions_by_account(budget_id, account_id)?),
+        Request::Payees { budget_id } => Payload::Payees(source.get_payees(budget_id)?),
+        Request::Categories { budget_id } => Payload::Categories(source.get_categories(budget_id)?),
+        Request::Save(edit) => {
+            source.save(edit)?;
+            Payload::Saved
+        }
+    })
+}
@@ -1,29 +1,84 @@
 use chrono::{DateTime, Duration, Local};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json;
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    fs, io,
-};
+use std::{collections::HashMap, fs, io};
 use ynab_openapi::{
     apis::{
-        accounts_api, budgets_api,
+        accounts_api, budgets_api, categories_api,
         configuration::{ApiKey, Configuration},
-        transactions_api,
+        months_api, payees_api, transactions_api,
+    },
+    models::{
+        Account, BudgetSummary, CategoryGroupWithCategories, MonthDetail, MonthSummary, Payee,
+        PostTransactionsWrapper, PutTransactionWrapper, TransactionDetail,
     },
-    models::{Account, BudgetSummary, TransactionDetail},
 };
 
-type ApiResult<T> = Result<T, Box<dyn std::error::Error>>;
+use super::{BudgetDataSource, DataError, Edit, Outbox, Status};
+
+pub type ApiResult<T> = Result<T, DataError>;
+
+/// How long to keep serving the cache after the network dropped out before trying it again
+const RECONNECT_INTERVAL_SECONDS: i64 = 30;
+
+/// The kinds of data YNAB serves, each with its own refresh duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Budgets,
+    Accounts,
+    Transactions,
+    Categories,
+    Payees,
+    Months,
+}
+
+impl Resource {
+    /// The resource a cache endpoint like `/budgets/{id}/accounts/{id}/transactions` returns
+    fn of(endpoint: &str) -> Self {
+        let segments: Vec<&str> = endpoint.trim_start_matches('/').split('/').collect();
+        match segments.as_slice() {
+            [.., "transactions"] => Resource::Transactions,
+            [_, _, "accounts", ..] => Resource::Accounts,
+            [_, _, "categories", ..] => Resource::Categories,
+            [_, _, "payees", ..] => Resource::Payees,
+            [_, _, "months", ..] => Resource::Months,
+            _ => Resource::Budgets,
+        }
+    }
+}
 
-#[derive(Debug)]
 pub struct YnabApi {
     cache: HashMap<String, CacheEntry>,
     config: Configuration,
     cache_hit: u32,
     cache_file: String,
     refresh_duration: Duration,
+    refresh_overrides: HashMap<Resource, Duration>,
     force_refresh: bool,
+    stale: Option<DataError>,
+    /// Set by `--offline`, never touch the network
+    offline: bool,
+    /// Set when a request failed to reach YNAB, cleared again after `RECONNECT_INTERVAL_SECONDS`
+    disconnected_at: Option<DateTime<Local>>,
+    outbox: Outbox,
+    /// Asked for a new token after YNAB rejected the current one
+    token_source: Option<TokenSource>,
+    unauthorized: bool,
+}
+
+type TokenSource = Box<dyn FnMut() -> Result<String, String> + Send>;
+
+/// Leaves out the configuration, it holds the access token
+impl std::fmt::Debug for YnabApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("YnabApi")
+            .field("base_path", &self.config.base_path)
+            .field("cache_file", &self.cache_file)
+            .field("cache_hit", &self.cache_hit)
+            .field("offline", &self.is_offline())
+            .field("outbox", &self.outbox)
+            .finish_non_exhaustive()
+    }
 }
 
 impl YnabApi {
@@ -32,59 +87,312 @@ impl YnabApi {
             .map(|f| serde_json::from_reader(io::BufReader::new(f)).unwrap_or_default())
             .unwrap_or_default();
 
-        let mut config = Configuration::new();
-        config.bearer_access_token = Some(token.to_string());
-        config.api_key = Some(ApiKey {
-            prefix: Some("Bearer".to_string()),
-            key: token.to_string(),
-        });
-
-        Self {
+        let mut api = Self {
             cache,
             cache_hit: 0,
-            config,
+            config: Configuration::new(),
             cache_file: cache_file.to_string(),
             refresh_duration,
+            refresh_overrides: HashMap::new(),
             force_refresh: false,
+            stale: None,
+            offline: false,
+            disconnected_at: None,
+            outbox: Outbox::load(&format!("{cache_file}.outbox")),
+            token_source: None,
+            unauthorized: false,
+        };
+        api.set_token(token);
+        api
+    }
+
+    fn set_token(&mut self, token: &str) {
+        self.config.bearer_access_token = Some(token.to_string());
+        self.config.api_key = Some(ApiKey {
+            prefix: Some("Bearer".to_string()),
+            key: token.to_string(),
+        });
+    }
+
+    /// Called for a new token after YNAB rejected the current one, so that a retry picks up a
+    /// token that was changed while the app was running, e.g. by rerunning `token_command`
+    pub fn set_token_source(
+        &mut self,
+        source: impl FnMut() -> Result<String, String> + Send + 'static,
+    ) {
+        self.token_source = Some(Box::new(source));
+    }
+
+    /// Asks the token source again after a 401. Its failure is the request's, so the error
+    /// popup says why and the retry asks once more.
+    fn reauthenticate(&mut self) -> ApiResult<()> {
+        if !std::mem::take(&mut self.unauthorized) {
+            return Ok(());
+        }
+        match self.token_source.as_mut().map(|source| source()) {
+            Some(Ok(token)) => self.set_token(&token),
+            Some(Err(e)) => {
+                self.unauthorized = true;
+                return Err(DataError::Token(e));
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Remembers failures that affect later requests
+    fn note_error(&mut self, e: &DataError) {
+        match e {
+            DataError::Network(_) => self.disconnected_at = Some(Local::now()),
+            DataError::Unauthorized => self.unauthorized = true,
+            _ => {}
         }
     }
 
-    fn get<'a, 's: 'a, T, F>(&'s mut self, endpoint: String, api_call: F) -> ApiResult<T>
+    /// Talk to another server than `https://api.ynab.com/v1`, e.g. a `MockServer`
+    pub fn set_base_path(&mut self, base_path: &str) {
+        self.config.base_path = base_path.trim_end_matches('/').to_string();
+    }
+
+    /// Keep `resource` cached for `duration` instead of the default refresh duration
+    pub fn set_refresh_duration(&mut self, resource: Resource, duration: Duration) {
+        self.refresh_overrides.insert(resource, duration);
+    }
+
+    fn refresh_duration(&self, endpoint: &str) -> Duration {
+        self.refresh_overrides
+            .get(&Resource::of(endpoint))
+            .copied()
+            .unwrap_or(self.refresh_duration)
+    }
+
+    /// Serve everything from the cache and queue edits, regardless of connectivity
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline || self.disconnected_at.is_some()
+    }
+
+    fn get<T, F>(&mut self, endpoint: String, api_call: F) -> ApiResult<T>
     where
         F: Fn(&Configuration) -> ApiResult<T>,
-        T: Serialize + Deserialize<'a>,
+        T: Serialize + DeserializeOwned,
     {
-        let cache_entry: &mut CacheEntry = match self.cache.entry(endpoint) {
-            Entry::Occupied(o) => o.into_mut(),
-            Entry::Vacant(v) => {
-                let ce = CacheEntry {
-                    datetime: Local::now(),
-                    response_json: serde_json::to_string(&api_call(&self.config)?)?,
-                };
-                v.insert(ce)
+        self.reauthenticate()?;
+        self.sync();
+        let offline = self.is_offline();
+
+        if !self.cache.contains_key(&endpoint) {
+            if offline {
+                return Err(DataError::Offline);
             }
-        };
+            let resp = api_call(&self.config).inspect_err(|e| self.note_error(e))?;
+            let ce = CacheEntry {
+                datetime: Local::now(),
+                response_json: serde_json::to_string(&resp)?,
+            };
+            self.cache.insert(endpoint.clone(), ce);
+        }
 
-        if Local::now() - cache_entry.datetime < self.refresh_duration && !self.force_refresh {
+        let cache_entry = &self.cache[&endpoint];
+        let fresh = Local::now() - cache_entry.datetime < self.refresh_duration(&endpoint);
+        if offline || (fresh && !self.force_refresh) {
             self.cache_hit += 1;
             return Ok(serde_json::from_str(&cache_entry.response_json)?);
         }
 
-        let resp = api_call(&self.config)?;
+        match api_call(&self.config) {
+            Ok(resp) => {
+                let response_json = serde_json::to_string(&resp)?;
+                if let Some(cache_entry) = self.cache.get_mut(&endpoint) {
+                    cache_entry.response_json = response_json;
+                    cache_entry.datetime = Local::now();
+                }
+                Ok(resp)
+            }
+            Err(e) => {
+                self.note_error(&e);
+                // Serve the expired entry rather than nothing, and remember why it is stale
+                self.stale = Some(e);
+                Ok(serde_json::from_str(&self.cache[&endpoint].response_json)?)
+            }
+        }
+    }
+
+    /// Tries the network again once the reconnect interval has passed, and replays the outbox
+    /// whenever YNAB is reachable
+    fn sync(&mut self) {
+        if self.offline {
+            return;
+        }
+        if let Some(disconnected_at) = self.disconnected_at {
+            if Local::now() - disconnected_at < Duration::seconds(RECONNECT_INTERVAL_SECONDS) {
+                return;
+            }
+            self.disconnected_at = None;
+        }
+
+        while let Some(edit) = self.outbox.front().cloned() {
+            let result = match self.check_conflict(&edit) {
+                Ok(Some(server)) => self.outbox.conflict(server),
+                Ok(None) => match self.send(&edit) {
+                    Ok(()) => self.outbox.pop(),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(()) => self.expire(edit.budget_id()),
+                // Nothing wrong with the edit itself, try again on the next request
+                Err(
+                    e @ (DataError::Network(_) | DataError::Unauthorized | DataError::RateLimited),
+                ) => {
+                    self.note_error(&e);
+                    return;
+                }
+                // YNAB refused the edit, e.g. the account no longer exists
+                Err(_) => {
+                    let _ = self.outbox.conflict(None);
+                }
+            }
+        }
+    }
+
+    /// Returns the server's version of the transaction if it changed since the edit was made,
+    /// `Some(None)` if it was deleted
+    fn check_conflict(&self, edit: &Edit) -> ApiResult<Option<Option<TransactionDetail>>> {
+        let (budget_id, original) = match edit {
+            Edit::Create { .. } | Edit::Import { .. } => return Ok(None),
+            Edit::Update {
+                budget_id,
+                original,
+                ..
+            }
+            | Edit::Delete {
+                budget_id,
+                original,
+            } => (budget_id, original),
+        };
+
+        match transactions_api::get_transaction_by_id(&self.config, budget_id, &original.id) {
+            Ok(response) if *response.data.transaction == *original => Ok(None),
+            Ok(response) => Ok(Some(Some(*response.data.transaction))),
+            Err(e) => match DataError::from(e) {
+                DataError::Api { status: 404, .. } => Ok(Some(None)),
+                e => Err(e),
+            },
+        }
+    }
+
+    fn send(&self, edit: &Edit) -> ApiResult<()> {
+        match edit {
+            Edit::Create {
+                budget_id,
+                transaction,
+            } => {
+                let mut data = PostTransactionsWrapper::new();
+                data.transaction = Some(Box::new(transaction.clone()));
+                transactions_api::create_transaction(&self.config, budget_id, data)?;
+            }
+            Edit::Update {
+                budget_id,
+                original,
+                transaction,
+            } => {
+                let data = PutTransactionWrapper::new(transaction.clone());
+                transactions_api::update_transaction(&self.config, budget_id, &original.id, data)?;
+            }
+            Edit::Delete {
+                budget_id,
+                original,
+            } => {
+                transactions_api::delete_transaction(&self.config, budget_id, &original.id)?;
+            }
+            Edit::Import {
+                budget_id,
+                transactions,
+            } => {
+                let mut data = PostTransactionsWrapper::new();
+                data.transactions = Some(transactions.clone());
+                transactions_api::create_transaction(&self.config, budget_id, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies an edit to every cached transaction list of its budget
+    fn patch_cache(&mut self, edit: &Edit) {
+        let (original, changes) = match edit {
+            Edit::Create { .. } | Edit::Import { .. } => return,
+            Edit::Update {
+                original,
+                transaction,
+                ..
+            } => (
+                original,
+                Some(serde_json::to_value(transaction).unwrap_or_default()),
+            ),
+            Edit::Delete { original, .. } => (original, None),
+        };
+
+        let prefix = format!("/budgets/{}/", edit.budget_id());
+        for (endpoint, entry) in self.cache.iter_mut() {
+            if !endpoint.starts_with(&prefix) || !endpoint.ends_with("/transactions") {
+                continue;
+            }
+            let Ok(mut response) = serde_json::from_str::<serde_json::Value>(&entry.response_json)
+            else {
+                continue;
+            };
+            let Some(transactions) = response["data"]["transactions"].as_array_mut() else {
+                continue;
+            };
 
-        cache_entry.response_json = serde_json::to_string(&resp)?;
+            let is_original = |t: &serde_json::Value| t["id"] == original.id.as_str();
+            match &changes {
+                Some(serde_json::Value::Object(changes)) => {
+                    for t in transactions.iter_mut().filter(|t| is_original(t)) {
+                        for (field, value) in changes {
+                            t[field] = value.clone();
+                        }
+                    }
+                }
+                _ => transactions.retain(|t| !is_original(t)),
+            }
 
-        Ok(resp)
+            if let Ok(json) = serde_json::to_string(&response) {
+                entry.response_json = json;
+            }
+        }
     }
 
-    pub fn get_budgets(&mut self) -> ApiResult<Vec<BudgetSummary>> {
+    /// Marks every cached response of a budget as expired, so the next request refetches it
+    fn expire(&mut self, budget_id: &str) {
+        let prefix = format!("/budgets/{budget_id}/");
+        for (endpoint, entry) in self.cache.iter_mut() {
+            if endpoint.starts_with(&prefix) {
+                entry.datetime -= self
+                    .refresh_overrides
+                    .get(&Resource::of(endpoint))
+                    .copied()
+                    .unwrap_or(self.refresh_duration);
+            }
+        }
+    }
+}
+
+impl BudgetDataSource for YnabApi {
+    fn get_budgets(&mut self) -> ApiResult<Vec<BudgetSummary>> {
         let endp = "/budgets".to_string();
         let response = self.get(endp, |config| Ok(budgets_api::get_budgets(config, None)?))?;
 
         Ok(response.data.budgets)
     }
 
-    pub fn get_accounts(&mut self, budget_id: &str) -> ApiResult<Vec<Account>> {
+    fn get_accounts(&mut self, budget_id: &str) -> ApiResult<Vec<Account>> {
         let endp = format!("/budgets/{budget_id}/accounts");
         let response = self.get(endp, |config| {
             Ok(accounts_api::get_accounts(config, budget_id, None)?)
@@ -93,7 +401,7 @@ impl YnabApi {
         Ok(response.data.accounts)
     }
 
-    pub fn get_transactions_by_account(
+    fn get_transactions_by_account(
         &mut self,
         budget_id: &str,
         account_id: &str,
@@ -111,7 +419,7 @@ impl YnabApi {
         Ok(ts)
     }
 
-    pub fn get_transactions(&mut self, budget_id: &str) -> ApiResult<Vec<TransactionDetail>> {
+    fn get_transactions(&mut self, budget_id: &str) -> ApiResult<Vec<TransactionDetail>> {
         let endp = format!("/budgets/{budget_id}/transactions");
 
         let response = self.get(endp, |config| {
@@ -123,6 +431,91 @@ impl YnabApi {
         ts.sort_by(|a, b| b.date.cmp(&a.date));
         Ok(ts)
     }
+
+    fn get_categories(&mut self, budget_id: &str) -> ApiResult<Vec<CategoryGroupWithCategories>> {
+        let endp = format!("/budgets/{budget_id}/categories");
+        let response = self.get(endp, |config| {
+            Ok(categories_api::get_categories(config, budget_id, None)?)
+        })?;
+
+        Ok(response.data.category_groups)
+    }
+
+    fn get_payees(&mut self, budget_id: &str) -> ApiResult<Vec<Payee>> {
+        let endp = format!("/budgets/{budget_id}/payees");
+        let response = self.get(endp, |config| {
+            Ok(payees_api::get_payees(config, budget_id, None)?)
+        })?;
+
+        Ok(response.data.payees)
+    }
+
+    fn get_months(&mut self, budget_id: &str) -> ApiResult<Vec<MonthSummary>> {
+        let endp = format!("/budgets/{budget_id}/months");
+        let response = self.get(endp, |config| {
+            Ok(months_api::get_budget_months(config, budget_id, None)?)
+        })?;
+
+        Ok(response.data.months)
+    }
+
+    fn get_month(&mut self, budget_id: &str, month: &str) -> ApiResult<MonthDetail> {
+        let endp = format!("/budgets/{budget_id}/months/{month}");
+        let response = self.get(endp, |config| {
+            Ok(months_api::get_budget_month(
+                config,
+                budget_id,
+                month.to_string(),
+            )?)
+        })?;
+
+        Ok(*response.data.month)
+    }
+
+    /// Sends an edit, or queues it in the outbox while offline. Either way the cached
+    /// transactions are then patched so the edit shows up immediately. An edit the server
+    /// rejects leaves the cache as it was.
+    fn save(&mut self, edit: &Edit) -> ApiResult<()> {
+        self.reauthenticate()?;
+        self.sync();
+
+        if self.is_offline() {
+            self.outbox.push(edit.clone())?;
+            self.patch_cache(edit);
+            return Ok(());
+        }
+
+        match self.send(edit) {
+            Ok(()) => {
+                self.patch_cache(edit);
+                self.expire(edit.budget_id());
+                Ok(())
+            }
+            Err(e @ DataError::Network(_)) => {
+                self.note_error(&e);
+                self.outbox.push(edit.clone())?;
+                self.patch_cache(edit);
+                Ok(())
+            }
+            Err(e) => {
+                self.note_error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// The error that caused the last response to be served from an expired cache entry
+    fn take_stale(&mut self) -> Option<DataError> {
+        self.stale.take()
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            offline: self.is_offline(),
+            pending: self.outbox.pending(),
+            conflicts: self.outbox.conflicts().len(),
+        }
+    }
 }
 
 impl Drop for YnabApi {
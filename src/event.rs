@@ -0,0 +1,121 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use std::{
+    collections::VecDeque,
+    io,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+/// Everything the event loop waits for, merged into one channel
+#[derive(Debug)]
+pub enum AppEvent {
+    /// Keys, the mouse, pastes and resizes from the terminal
+    Input(Event),
+    /// The worker finished a response, `App::receive` collects it
+    Response,
+    /// Reading the terminal failed, which ends `App::run`
+    InputError(io::Error),
+}
+
+/// Where the app gets its events from: the terminal and the worker, or a script in tests
+pub trait EventSource {
+    /// Waits up to `timeout` for the next event, or until there is one if `None`
+    fn next(&mut self, timeout: Option<Duration>) -> io::Result<Option<AppEvent>>;
+
+    /// Whether the source has run out of events, which ends `App::run`
+    fn is_done(&self) -> bool {
+        false
+    }
+}
+
+/// Reads the terminal on its own thread, so that waiting for a key and for the worker are the
+/// same blocking `recv`
+#[derive(Debug)]
+pub struct TerminalEvents {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+}
+
+impl TerminalEvents {
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let input = sender.clone();
+        thread::spawn(move || loop {
+            let event = match event::read() {
+                Ok(event) => AppEvent::Input(event),
+                Err(e) => {
+                    let _ = input.send(AppEvent::InputError(e));
+                    break;
+                }
+            };
+            if input.send(event).is_err() {
+                break;
+            }
+        });
+        Self { sender, receiver }
+    }
+
+    /// Sends into the same channel, for the worker to wake the loop with
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+}
+
+impl EventSource for TerminalEvents {
+    fn next(&mut self, timeout: Option<Duration>) -> io::Result<Option<AppEvent>> {
+        // The channel stays open while `self` holds a sender
+        Ok(match timeout {
+            Some(timeout) => self.receiver.recv_timeout(timeout).ok(),
+            None => self.receiver.recv().ok(),
+        })
+    }
+}
+
+/// Replays a fixed sequence of events, without waiting
+#[derive(Debug, Default, Clone)]
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    /// One key press per character, `\n` is Enter and `\x1b` is Esc
+    pub fn keys(keys: &str) -> Self {
+        let mut events = Self::default();
+        events.push_keys(keys);
+        events
+    }
+
+    pub fn push_keys(&mut self, keys: &str) -> &mut Self {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            self.push(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+        }
+        self
+    }
+
+    pub fn push(&mut self, event: Event) -> &mut Self {
+        self.events.push_back(event);
+        self
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next(&mut self, _timeout: Option<Duration>) -> io::Result<Option<AppEvent>> {
+        Ok(self.events.pop_front().map(AppEvent::Input))
+    }
+
+    fn is_done(&self) -> bool {
+        self.events.is_empty()
+    }
+}
@@ -0,0 +1,309 @@
+//! Writes transactions to files for spreadsheets and plain-text accounting, from `:export` in
+//! the transaction table and `ynab_tui export`
+use clap::ValueEnum;
+use std::{collections::BTreeSet, io, path::Path, str::FromStr};
+use uuid::Uuid;
+use ynab_openapi::models::{transaction_detail::Cleared, Account, AccountType, TransactionDetail};
+
+use crate::{
+    components::Column,
+    config::{self, Export},
+    util::plain_amount,
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    /// One JSON transaction per line
+    Jsonl,
+    /// A journal for ledger and hledger
+    Ledger,
+    Beancount,
+}
+
+impl Format {
+    /// Guesses the format from a file extension, e.g. `.journal` or `.beancount`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "jsonl" | "json" => Ok(Format::Jsonl),
+            "ledger" | "hledger" | "journal" => Ok(Format::Ledger),
+            "beancount" | "bean" => Ok(Format::Beancount),
+            _ => Err(format!(
+                "Unknown export format `{s}`, expected csv, jsonl, ledger or beancount"
+            )),
+        }
+    }
+}
+
+/// Transactions to export, with the accounts of the budget to name transfers
+pub struct Transactions<'a> {
+    pub accounts: &'a [Account],
+    pub transactions: &'a [TransactionDetail],
+}
+
+/// One side of a ledger transaction
+struct Posting {
+    account: String,
+    amount: i64,
+    memo: Option<String>,
+}
+
+impl<'a> Transactions<'a> {
+    pub fn new(accounts: &'a [Account], transactions: &'a [TransactionDetail]) -> Self {
+        Self {
+            accounts,
+            transactions,
+        }
+    }
+
+    pub fn write(&self, format: Format, out: &mut impl io::Write) -> io::Result<()> {
+        let config = &config::get().export;
+        match format {
+            Format::Csv => self.write_csv(config, out),
+            Format::Jsonl => self.write_jsonl(out),
+            Format::Ledger => self.write_ledger(config, out),
+            Format::Beancount => self.write_beancount(config, out),
+        }
+    }
+
+    fn write_csv(&self, config: &Export, out: &mut impl io::Write) -> io::Result<()> {
+        let columns = config
+            .columns
+            .as_deref()
+            .unwrap_or_else(|| config::get().columns());
+
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record(columns.iter().map(Column::title))?;
+        for t in self.transactions {
+            writer.write_record(columns.iter().map(|c| match c {
+                Column::Amount => plain_amount(t.amount),
+                c => c.cell(t),
+            }))?;
+        }
+        writer.flush()
+    }
+
+    fn write_jsonl(&self, out: &mut impl io::Write) -> io::Result<()> {
+        for t in self.transactions {
+            serde_json::to_writer(&mut *out, t)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn write_ledger(&self, config: &Export, out: &mut impl io::Write) -> io::Result<()> {
+        for t in self.journal() {
+            let status = if t.cleared == Cleared::Uncleared {
+                "!"
+            } else {
+                "*"
+            };
+            writeln!(
+                out,
+                "{} {status} {}",
+                t.date,
+                t.payee_name.as_deref().unwrap_or("")
+            )?;
+            if let Some(memo) = t.memo.as_deref().filter(|m| !m.is_empty()) {
+                writeln!(out, "    ; {memo}")?;
+            }
+            for posting in self.postings(t, config) {
+                write!(
+                    out,
+                    "    {:<40}  {:>12} {}",
+                    posting.account,
+                    plain_amount(posting.amount),
+                    config.commodity
+                )?;
+                match posting.memo {
+                    Some(memo) => writeln!(out, "  ; {memo}")?,
+                    None => writeln!(out)?,
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn write_beancount(&self, config: &Export, out: &mut impl io::Write) -> io::Result<()> {
+        let journal = self.journal();
+        let postings: Vec<Vec<Posting>> = journal
+            .iter()
+            .map(|t| {
+                let mut postings = self.postings(t, config);
+                for p in &mut postings {
+                    p.account = beancount_account(&p.account);
+                }
+                postings
+            })
+            .collect();
+
+        // Beancount refuses postings to accounts that were never opened
+        if let Some(first) = journal.iter().map(|t| &t.date).min() {
+            let accounts: BTreeSet<&str> = postings
+                .iter()
+                .flatten()
+                .map(|p| p.account.as_str())
+                .collect();
+            for account in accounts {
+                writeln!(out, "{first} open {account}")?;
+            }
+            writeln!(out)?;
+        }
+
+        for (t, postings) in journal.iter().zip(postings) {
+            let flag = if t.cleared == Cleared::Uncleared {
+                "!"
+            } else {
+                "*"
+            };
+            writeln!(
+                out,
+                "{} {flag} {} {}",
+                t.date,
+                quote(t.payee_name.as_deref().unwrap_or("")),
+                quote(t.memo.as_deref().unwrap_or(""))
+            )?;
+            for posting in postings {
+                writeln!(
+                    out,
+                    "  {:<40}  {:>12} {}",
+                    posting.account,
+                    plain_amount(posting.amount),
+                    config.commodity
+                )?;
+                if let Some(memo) = posting.memo {
+                    writeln!(out, "    memo: {}", quote(&memo))?;
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// The transactions oldest first, with each transfer only once. Both sides of a transfer
+    /// are separate YNAB transactions, the inflow is dropped when its outflow is exported too.
+    fn journal(&self) -> Vec<&'a TransactionDetail> {
+        let ids: BTreeSet<&str> = self.transactions.iter().map(|t| t.id.as_str()).collect();
+        let mut journal: Vec<&TransactionDetail> = self
+            .transactions
+            .iter()
+            .filter(|t| !t.deleted)
+            .filter(|t| {
+                let other = t.transfer_transaction_id.as_deref();
+                t.amount < 0 || !other.is_some_and(|id| ids.contains(id))
+            })
+            .collect();
+        journal.sort_by(|a, b| a.date.cmp(&b.date));
+        journal
+    }
+
+    /// The account's posting and the balancing ones, one per subtransaction of a split
+    fn postings(&self, t: &TransactionDetail, config: &Export) -> Vec<Posting> {
+        let mut postings = vec![Posting {
+            account: self.account_name(t.account_id, &t.account_name, config),
+            amount: t.amount,
+            memo: None,
+        }];
+
+        let subtransactions: Vec<_> = t.subtransactions.iter().filter(|s| !s.deleted).collect();
+        if subtransactions.is_empty() {
+            postings.push(Posting {
+                account: self.other_side(t.transfer_account_id, t.category_name.as_deref(), config),
+                amount: -t.amount,
+                memo: None,
+            });
+        }
+        for s in subtransactions {
+            postings.push(Posting {
+                account: self.other_side(s.transfer_account_id, s.category_name.as_deref(), config),
+                amount: -s.amount,
+                memo: s.memo.clone().filter(|m| !m.is_empty()),
+            });
+        }
+        postings
+    }
+
+    /// The account a transfer went to, or the category the money was spent from or given to
+    fn other_side(
+        &self,
+        transfer: Option<Uuid>,
+        category: Option<&str>,
+        config: &Export,
+    ) -> String {
+        if let Some(id) = transfer {
+            let name = self
+                .accounts
+                .iter()
+                .find(|a| a.id == id)
+                .map_or("Transfer", |a| a.name.as_str());
+            return self.account_name(id, name, config);
+        }
+
+        match category {
+            Some(name) if config.categories.contains_key(name) => config.categories[name].clone(),
+            Some(name) if name.starts_with("Inflow") => "Income:Ready to Assign".to_string(),
+            None | Some("Uncategorized") => "Expenses:Uncategorized".to_string(),
+            Some(name) => format!("Expenses:{name}"),
+        }
+    }
+
+    fn account_name(&self, id: Uuid, name: &str, config: &Export) -> String {
+        if let Some(mapped) = config.accounts.get(name) {
+            return mapped.clone();
+        }
+        let liability = self
+            .accounts
+            .iter()
+            .find(|a| a.id == id)
+            .is_some_and(|a| is_liability(a.r#type));
+        if liability {
+            format!("Liabilities:{name}")
+        } else {
+            format!("Assets:{name}")
+        }
+    }
+}
+
+fn is_liability(account_type: AccountType) -> bool {
+    !matches!(
+        account_type,
+        AccountType::Checking | AccountType::Savings | AccountType::Cash | AccountType::OtherAsset
+    )
+}
+
+/// Beancount only allows capitalized components of letters, digits and dashes, e.g.
+/// `Expenses:Dining out` becomes `Expenses:Dining-out`
+fn beancount_account(account: &str) -> String {
+    account
+        .split(':')
+        .map(|component| {
+            let cleaned: String = component
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                .collect();
+            let cleaned = cleaned.trim_matches('-');
+            let mut chars = cleaned.chars();
+            match chars.next() {
+                Some(first) if first.is_alphanumeric() => {
+                    first.to_uppercase().chain(chars).collect()
+                }
+                _ => "Other".to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
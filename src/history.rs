@@ -0,0 +1,154 @@
+//! Where the user has been: the pages open above the first one, the pages gone back from, and
+//! a jump list of the trails left behind when switching budgets
+use tui::backend::Backend;
+
+use crate::page::Page;
+
+/// How many trails the jump list keeps, the oldest are dropped first
+pub const MAX_JUMPS: usize = 20;
+
+/// A line of pages opened from one another above the root, and the ones gone back from
+struct Trail<B: Backend> {
+    pages: Vec<Box<dyn Page<B>>>,
+    /// Pages gone back from, the next one to go forward to last
+    forward: Vec<Box<dyn Page<B>>>,
+}
+
+impl<B: Backend> Trail<B> {
+    fn new(pages: Vec<Box<dyn Page<B>>>) -> Self {
+        Self {
+            pages,
+            forward: vec![],
+        }
+    }
+}
+
+/// Browser style history. Pages are moved rather than rebuilt, so going back to one shows it
+/// with the selection, filter and scroll it was left with.
+pub struct History<B: Backend> {
+    /// The page every trail starts from
+    root: Box<dyn Page<B>>,
+    trails: Vec<Trail<B>>,
+    /// The trail on screen
+    current: usize,
+}
+
+impl<B: Backend> History<B> {
+    pub fn new(root: Box<dyn Page<B>>) -> Self {
+        Self {
+            root,
+            trails: vec![Trail::new(vec![])],
+            current: 0,
+        }
+    }
+
+    fn trail(&self) -> &Trail<B> {
+        &self.trails[self.current]
+    }
+
+    fn trail_mut(&mut self) -> &mut Trail<B> {
+        &mut self.trails[self.current]
+    }
+
+    /// The page on screen
+    pub fn page(&self) -> &dyn Page<B> {
+        self.trail().pages.last().unwrap_or(&self.root).as_ref()
+    }
+
+    pub fn page_mut(&mut self) -> &mut Box<dyn Page<B>> {
+        let trail = &mut self.trails[self.current];
+        trail.pages.last_mut().unwrap_or(&mut self.root)
+    }
+
+    /// The pages from the root to the one on screen
+    pub fn path(&self) -> impl Iterator<Item = &dyn Page<B>> {
+        let pages = self.trail().pages.iter().map(AsRef::as_ref);
+        std::iter::once(self.root.as_ref()).chain(pages)
+    }
+
+    /// How many pages are open above the root
+    pub fn depth(&self) -> usize {
+        self.trail().pages.len()
+    }
+
+    /// Every page the history holds, including the ones gone back from and the other trails,
+    /// so they stay up to date until they are shown again
+    pub fn pages_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Page<B>>> {
+        let trails = self
+            .trails
+            .iter_mut()
+            .flat_map(|t| t.pages.iter_mut().chain(t.forward.iter_mut()));
+        std::iter::once(&mut self.root).chain(trails)
+    }
+
+    /// Opens `page` on top of the current one. The pages that could be gone forward to are
+    /// dropped.
+    pub fn push(&mut self, page: Box<dyn Page<B>>) {
+        let trail = self.trail_mut();
+        trail.forward.clear();
+        trail.pages.push(page);
+    }
+
+    /// Returns false if the root is on screen
+    pub fn back(&mut self) -> bool {
+        let trail = self.trail_mut();
+        match trail.pages.pop() {
+            Some(page) => {
+                trail.forward.push(page);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns false if there is nothing to go forward to
+    pub fn forward(&mut self) -> bool {
+        let trail = self.trail_mut();
+        match trail.forward.pop() {
+            Some(page) => {
+                trail.pages.push(page);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes back until `depth` pages are open above the root, so each of them can still be gone
+    /// forward to
+    pub fn go_to(&mut self, depth: usize) {
+        while self.depth() > depth && self.back() {}
+    }
+
+    /// Starts a new trail from the root with `pages`, after the current one. Trails that could
+    /// be jumped forward to are dropped, as are the oldest beyond `MAX_JUMPS`.
+    pub fn jump(&mut self, pages: Vec<Box<dyn Page<B>>>) {
+        self.trails.truncate(self.current + 1);
+        // A trail with nothing open is not worth jumping back to
+        if self.depth() == 0 {
+            self.trails.pop();
+        }
+        self.trails.push(Trail::new(pages));
+        if self.trails.len() > MAX_JUMPS {
+            self.trails.remove(0);
+        }
+        self.current = self.trails.len() - 1;
+    }
+
+    /// Returns false if there is no older trail
+    pub fn jump_back(&mut self) -> bool {
+        let Some(previous) = self.current.checked_sub(1) else {
+            return false;
+        };
+        self.current = previous;
+        true
+    }
+
+    /// Returns false if there is no newer trail
+    pub fn jump_forward(&mut self) -> bool {
+        if self.current + 1 >= self.trails.len() {
+            return false;
+        }
+        self.current += 1;
+        true
+    }
+}
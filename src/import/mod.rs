@@ -0,0 +1,227 @@
+//! Reads bank statements and decides which of their rows are new to a YNAB account
+mod parse;
+pub use parse::parse_amount;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+use uuid::Uuid;
+use ynab_openapi::models::{save_transaction::Cleared, SaveTransaction, TransactionDetail};
+
+use crate::config::{self, Bank};
+
+/// Rows this many days apart with the same amount are flagged as likely duplicates
+pub const FUZZY_DAYS: i64 = 3;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    Csv,
+    Ofx,
+    Qif,
+}
+
+impl StatementFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" | "txt" => Some(StatementFormat::Csv),
+            "ofx" | "qfx" => Some(StatementFormat::Ofx),
+            "qif" => Some(StatementFormat::Qif),
+            _ => None,
+        }
+    }
+}
+
+/// A column of a CSV statement, by its header or its index counted from 0
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+/// A transaction as the bank reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementRow {
+    pub date: NaiveDate,
+    /// In milliunits, negative for money leaving the account
+    pub amount: i64,
+    pub payee: String,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Read(io::Error),
+    /// The file has no known extension and the bank sets no `format`
+    UnknownFormat,
+    UnknownBank(String, Vec<String>),
+    /// A line of the statement that could not be read
+    Parse(usize, String),
+    /// The statement lacks a column needed to import it
+    Column(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Read(e) => write!(f, "Could not read the statement: {e}"),
+            ImportError::UnknownFormat => {
+                write!(
+                    f,
+                    "Unknown statement format, expected a .csv, .ofx or .qif file"
+                )
+            }
+            ImportError::UnknownBank(name, known) if known.is_empty() => {
+                write!(f, "Unknown bank `{name}`, no banks are configured")
+            }
+            ImportError::UnknownBank(name, known) => {
+                write!(
+                    f,
+                    "Unknown bank `{name}`, expected one of {}",
+                    known.join(", ")
+                )
+            }
+            ImportError::Parse(line, e) => write!(f, "Line {line}: {e}"),
+            ImportError::Column(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Read(e)
+    }
+}
+
+/// The bank named in the config, or the defaults, which guess the columns of a CSV file
+pub fn bank(name: Option<&str>) -> Result<Bank, ImportError> {
+    let banks = &config::get().banks;
+    match name {
+        None => Ok(Bank::default()),
+        Some(name) => banks.get(name).cloned().ok_or_else(|| {
+            ImportError::UnknownBank(name.to_string(), banks.keys().cloned().collect())
+        }),
+    }
+}
+
+/// Reads the statement at `path` with the layout of `bank`
+pub fn read(path: &Path, bank: &Bank) -> Result<Vec<StatementRow>, ImportError> {
+    let format = bank
+        .format
+        .or_else(|| StatementFormat::from_path(path))
+        .ok_or(ImportError::UnknownFormat)?;
+    let bytes = fs::read(path)?;
+    // Statements are often Latin-1, which only matters for payee names
+    let text = String::from_utf8(bytes)
+        .unwrap_or_else(|e| e.into_bytes().iter().map(|&b| b as char).collect());
+    parse(&text, format, bank)
+}
+
+pub fn parse(
+    text: &str,
+    format: StatementFormat,
+    bank: &Bank,
+) -> Result<Vec<StatementRow>, ImportError> {
+    match format {
+        StatementFormat::Csv => parse::csv(text, bank),
+        StatementFormat::Ofx => parse::ofx(text),
+        StatementFormat::Qif => parse::qif(text, bank),
+    }
+}
+
+/// Why a statement row should not be imported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Duplicate {
+    /// The row was imported before, its `import_id` is in the account
+    Imported,
+    /// The account has a transaction with the same amount a few days apart, e.g. one entered by
+    /// hand before the statement arrived
+    Similar { date: String, payee: String },
+}
+
+/// A statement row, checked against the account it goes into
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRow {
+    pub row: StatementRow,
+    /// In the format YNAB uses for its own file imports, so they recognize each other's rows
+    pub import_id: String,
+    pub duplicate: Option<Duplicate>,
+    /// Whether the row will be created, duplicates start out deselected
+    pub selected: bool,
+}
+
+/// Checks `rows` against the transactions already in the account
+pub fn preview(rows: Vec<StatementRow>, existing: &[TransactionDetail]) -> Vec<ImportRow> {
+    let mut occurrences: HashMap<(i64, NaiveDate), usize> = HashMap::new();
+    let mut claimed = vec![false; existing.len()];
+
+    let mut preview: Vec<ImportRow> = rows
+        .into_iter()
+        .map(|row| {
+            let occurrence = occurrences.entry((row.amount, row.date)).or_default();
+            *occurrence += 1;
+            let import_id = format!("YNAB:{}:{}:{occurrence}", row.amount, row.date);
+
+            let imported = existing
+                .iter()
+                .position(|t| !t.deleted && t.import_id.as_deref() == Some(import_id.as_str()));
+            if let Some(i) = imported {
+                claimed[i] = true;
+            }
+
+            ImportRow {
+                row,
+                import_id,
+                duplicate: imported.map(|_| Duplicate::Imported),
+                selected: imported.is_none(),
+            }
+        })
+        .collect();
+
+    // Only once the exact matches have claimed their transactions
+    for import in preview.iter_mut().filter(|i| i.duplicate.is_none()) {
+        let closest = existing
+            .iter()
+            .enumerate()
+            .filter(|(i, t)| !claimed[*i] && !t.deleted && t.amount == import.row.amount)
+            .filter_map(|(i, t)| {
+                let date = NaiveDate::parse_from_str(&t.date, "%Y-%m-%d").ok()?;
+                let days = (date - import.row.date).num_days().abs();
+                (days <= FUZZY_DAYS).then_some((days, i))
+            })
+            .min();
+
+        if let Some((_, i)) = closest {
+            claimed[i] = true;
+            import.duplicate = Some(Duplicate::Similar {
+                date: existing[i].date.clone(),
+                payee: existing[i].payee_name.clone().unwrap_or_default(),
+            });
+            import.selected = false;
+        }
+    }
+
+    preview
+}
+
+/// The selected rows, ready for `BudgetDataSource::import_transactions`
+pub fn save_transactions(rows: &[ImportRow], account_id: Uuid) -> Vec<SaveTransaction> {
+    rows.iter()
+        .filter(|r| r.selected)
+        .map(|r| SaveTransaction {
+            account_id: Some(account_id),
+            date: Some(r.row.date.to_string()),
+            amount: Some(r.row.amount),
+            // The lengths YNAB accepts
+            payee_name: Some(r.row.payee.chars().take(50).collect()),
+            memo: r.row.memo.as_ref().map(|m| m.chars().take(200).collect()),
+            cleared: Some(Cleared::Cleared),
+            approved: Some(false),
+            import_id: Some(r.import_id.clone()),
+            ..Default::default()
+        })
+        .collect()
+}
@@ -0,0 +1,274 @@
+//! Parsers for the statement formats banks export
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+use super::{ColumnRef, ImportError, StatementRow};
+use crate::config::Bank;
+
+/// Tried in order when a bank sets no `date_format`. Two digit years go first, `%Y` would read
+/// `24` as the year 24.
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%m/%d/%y", "%m/%d/%Y", "%d.%m.%y", "%d.%m.%Y", "%Y/%m/%d", "%Y%m%d",
+];
+
+pub fn csv(text: &str, bank: &Bank) -> Result<Vec<StatementRow>, ImportError> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(bank.delimiter as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut records = reader.records().skip(bank.skip_rows);
+    let headers: Vec<String> = if bank.headers {
+        match records.next() {
+            Some(header) => header
+                .map_err(|e| ImportError::Parse(bank.skip_rows + 1, e.to_string()))?
+                .iter()
+                .map(|h| h.trim().to_string())
+                .collect(),
+            None => return Ok(vec![]),
+        }
+    } else {
+        vec![]
+    };
+    let columns = Columns::new(bank, &headers)?;
+
+    let mut rows = vec![];
+    for record in records {
+        let record = record.map_err(|e| ImportError::Parse(line_of(&e), e.to_string()))?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let field = |i: usize| record.get(i).unwrap_or("").trim();
+        let date =
+            parse_date(field(columns.date), bank.date_format.as_deref()).ok_or_else(|| {
+                ImportError::Parse(line, format!("Invalid date `{}`", field(columns.date)))
+            })?;
+        let amount = match columns.amount {
+            Amount::Signed(i) => parse_amount(field(i), bank.decimal_comma).ok_or_else(|| {
+                ImportError::Parse(line, format!("Invalid amount `{}`", field(i)))
+            })?,
+            Amount::Split { inflow, outflow } => {
+                let inflow = parse_amount(field(inflow), bank.decimal_comma).unwrap_or(0);
+                let outflow = parse_amount(field(outflow), bank.decimal_comma).unwrap_or(0);
+                inflow.abs() - outflow.abs()
+            }
+        };
+
+        rows.push(StatementRow {
+            date,
+            amount: if bank.negate { -amount } else { amount },
+            payee: columns.payee.map(field).unwrap_or("").to_string(),
+            memo: columns
+                .memo
+                .map(field)
+                .filter(|m| !m.is_empty())
+                .map(str::to_string),
+        });
+    }
+    Ok(rows)
+}
+
+fn line_of(e: &::csv::Error) -> usize {
+    e.position().map_or(0, |p| p.line() as usize)
+}
+
+enum Amount {
+    Signed(usize),
+    Split { inflow: usize, outflow: usize },
+}
+
+/// The indices of the columns a CSV statement is read from
+struct Columns {
+    date: usize,
+    amount: Amount,
+    payee: Option<usize>,
+    memo: Option<usize>,
+}
+
+impl Columns {
+    fn new(bank: &Bank, headers: &[String]) -> Result<Self, ImportError> {
+        let find = |column: &Option<ColumnRef>, guesses: &[&str]| -> Result<Option<usize>, _> {
+            match column {
+                Some(ColumnRef::Index(i)) => Ok(Some(*i)),
+                Some(ColumnRef::Name(name)) => headers
+                    .iter()
+                    .position(|h| h.eq_ignore_ascii_case(name))
+                    .map(Some)
+                    .ok_or_else(|| ImportError::Column(format!("No column `{name}`"))),
+                None => Ok(guesses.iter().find_map(|guess| {
+                    headers
+                        .iter()
+                        .position(|h| h.to_lowercase().contains(guess))
+                })),
+            }
+        };
+
+        let date = find(&bank.date, &["date"])?
+            .ok_or_else(|| ImportError::Column("Set `date` to the column of the date".into()))?;
+        let amount = find(&bank.amount, &["amount"])?;
+        let inflow = find(&bank.inflow, &["inflow", "credit", "deposit"])?;
+        let outflow = find(&bank.outflow, &["outflow", "debit", "withdrawal"])?;
+        let amount = match (amount, inflow, outflow) {
+            (Some(i), _, _) => Amount::Signed(i),
+            (None, Some(inflow), Some(outflow)) => Amount::Split { inflow, outflow },
+            _ => {
+                return Err(ImportError::Column(
+                    "Set `amount`, or `inflow` and `outflow`, to the columns of the amounts".into(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            date,
+            amount,
+            payee: find(&bank.payee, &["payee", "description", "name"])?,
+            memo: find(&bank.memo, &["memo", "note", "reference"])?,
+        })
+    }
+}
+
+/// OFX 1.x is SGML that leaves most tags unclosed, 2.x is XML. Reading the values after the
+/// tags inside each `<STMTTRN>` works for both.
+pub fn ofx(text: &str) -> Result<Vec<StatementRow>, ImportError> {
+    let mut rows = vec![];
+    let mut fields: Option<HashMap<String, String>> = None;
+
+    // The line each segment starts on, counted as the segments go by
+    let mut next_line = 1;
+    for segment in text.split('<') {
+        let line = next_line;
+        next_line += segment.matches('\n').count();
+        let Some((tag, value)) = segment.split_once('>') else {
+            continue;
+        };
+        let tag = tag.trim().to_uppercase();
+        match tag.as_str() {
+            "STMTTRN" => fields = Some(HashMap::new()),
+            "/STMTTRN" => {
+                let Some(fields) = fields.take() else {
+                    continue;
+                };
+                let get = |tag: &str| fields.get(tag).map(String::as_str).unwrap_or("");
+                let date = get("DTPOSTED");
+                let date = date
+                    .get(..8)
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+                    .ok_or_else(|| {
+                        ImportError::Parse(line, format!("Invalid DTPOSTED `{date}`"))
+                    })?;
+                let amount = parse_amount(get("TRNAMT"), false).ok_or_else(|| {
+                    ImportError::Parse(line, format!("Invalid TRNAMT `{}`", get("TRNAMT")))
+                })?;
+                let payee = match get("NAME") {
+                    "" => get("PAYEE"),
+                    name => name,
+                };
+
+                rows.push(StatementRow {
+                    date,
+                    amount,
+                    payee: payee.to_string(),
+                    memo: Some(get("MEMO").to_string()).filter(|m| !m.is_empty()),
+                });
+            }
+            tag if !tag.starts_with('/') => {
+                if let Some(fields) = &mut fields {
+                    fields.insert(tag.to_string(), unescape(value.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// QIF records are lines starting with a field code, ended by `^`
+pub fn qif(text: &str, bank: &Bank) -> Result<Vec<StatementRow>, ImportError> {
+    let mut rows = vec![];
+    let (mut date, mut amount, mut payee, mut memo) = (None, None, String::new(), None);
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        let Some(code) = line.chars().next() else {
+            continue;
+        };
+        let value = line[code.len_utf8()..].trim();
+        match code {
+            'D' => {
+                // Quicken writes `1/ 5'24` for 2024-01-05
+                let normalized = value.replace(' ', "").replace('\'', "/");
+                date = Some(
+                    parse_date(&normalized, bank.date_format.as_deref()).ok_or_else(|| {
+                        ImportError::Parse(i + 1, format!("Invalid date `{value}`"))
+                    })?,
+                );
+            }
+            'T' | 'U' => {
+                amount = Some(parse_amount(value, bank.decimal_comma).ok_or_else(|| {
+                    ImportError::Parse(i + 1, format!("Invalid amount `{value}`"))
+                })?);
+            }
+            'P' => payee = value.to_string(),
+            'M' => memo = Some(value.to_string()).filter(|m| !m.is_empty()),
+            '^' => {
+                let (Some(date), Some(amount)) = (date.take(), amount.take()) else {
+                    return Err(ImportError::Parse(
+                        i + 1,
+                        "Record without date or amount".into(),
+                    ));
+                };
+                rows.push(StatementRow {
+                    date,
+                    amount: if bank.negate { -amount } else { amount },
+                    payee: std::mem::take(&mut payee),
+                    memo: memo.take(),
+                });
+            }
+            // `!Type:Bank` headers, categories, splits and cleared flags
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
+
+fn parse_date(s: &str, format: Option<&str>) -> Option<NaiveDate> {
+    match format {
+        Some(format) => NaiveDate::parse_from_str(s, format).ok(),
+        None => DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(s, format).ok()),
+    }
+}
+
+/// Parses amounts like `-1,234.56`, `(12.30)`, `$5` or, with `decimal_comma`, `1.234,56`, into
+/// milliunits
+pub fn parse_amount(s: &str, decimal_comma: bool) -> Option<i64> {
+    let s = s.trim();
+    let negative = s.starts_with('-') || s.ends_with('-') || s.starts_with('(');
+    let digits: String = s
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        .collect();
+    let digits = if decimal_comma {
+        digits.replace('.', "").replace(',', ".")
+    } else {
+        digits.replace(',', "")
+    };
+    if !digits.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let milliunits = (digits.parse::<f64>().ok()? * 1000.0).round() as i64;
+    Some(if negative { -milliunits } else { milliunits })
+}
@@ -0,0 +1,388 @@
+//! Every action a key can trigger, the keys bound to them by default and in the config, and
+//! the help popup generated from them
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::config::{self, parse_key, Keys};
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    Help,
+    Command,
+    FindBudget,
+    Back,
+    Forward,
+    JumpBack,
+    JumpForward,
+    Breadcrumbs,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PrevTab,
+    Refresh,
+    Cancel,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Select,
+    Filter,
+    Find,
+    Toggle,
+    SelectNew,
+    Create,
+    ToggleSide,
+    ToggleDetail,
+    WidenSide,
+    NarrowSide,
+    GrowDetail,
+    ShrinkDetail,
+    ResetLayout,
+    FollowTransfer,
+    ToggleCleared,
+    Approve,
+    Edit,
+    Delete,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::Help,
+        Action::Command,
+        Action::FindBudget,
+        Action::Back,
+        Action::Forward,
+        Action::JumpBack,
+        Action::JumpForward,
+        Action::Breadcrumbs,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::Refresh,
+        Action::Cancel,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Select,
+        Action::Filter,
+        Action::Find,
+        Action::Toggle,
+        Action::SelectNew,
+        Action::Create,
+        Action::ToggleSide,
+        Action::ToggleDetail,
+        Action::WidenSide,
+        Action::NarrowSide,
+        Action::GrowDetail,
+        Action::ShrinkDetail,
+        Action::ResetLayout,
+        Action::FollowTransfer,
+        Action::ToggleCleared,
+        Action::Approve,
+        Action::Edit,
+        Action::Delete,
+    ];
+
+    /// Handled by the app on every page, unless the page is reading text
+    pub const GLOBAL: &'static [Action] = &[
+        Action::Quit,
+        Action::Help,
+        Action::Command,
+        Action::FindBudget,
+        Action::Back,
+        Action::Forward,
+        Action::JumpBack,
+        Action::JumpForward,
+        Action::Breadcrumbs,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PrevTab,
+    ];
+
+    /// The name used in `[keybindings]`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Command => "command",
+            Action::FindBudget => "find_budget",
+            Action::Back => "back",
+            Action::Forward => "forward",
+            Action::JumpBack => "jump_back",
+            Action::JumpForward => "jump_forward",
+            Action::Breadcrumbs => "breadcrumbs",
+            Action::NewTab => "new_tab",
+            Action::CloseTab => "close_tab",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::Refresh => "refresh",
+            Action::Cancel => "cancel",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Select => "select",
+            Action::Filter => "filter",
+            Action::Find => "find",
+            Action::Toggle => "toggle",
+            Action::SelectNew => "select_new",
+            Action::Create => "create",
+            Action::ToggleSide => "toggle_side",
+            Action::ToggleDetail => "toggle_detail",
+            Action::WidenSide => "widen_side",
+            Action::NarrowSide => "narrow_side",
+            Action::GrowDetail => "grow_detail",
+            Action::ShrinkDetail => "shrink_detail",
+            Action::ResetLayout => "reset_layout",
+            Action::FollowTransfer => "follow_transfer",
+            Action::ToggleCleared => "toggle_cleared",
+            Action::Approve => "approve",
+            Action::Edit => "edit",
+            Action::Delete => "delete",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Show this help",
+            Action::Command => "Run a command, e.g. :budget",
+            Action::FindBudget => "Find a budget",
+            Action::Back => "Go back",
+            Action::Forward => "Go forward",
+            Action::JumpBack => "Jump to the previous trail",
+            Action::JumpForward => "Jump to the next trail",
+            Action::Breadcrumbs => "Go back to a page in the path",
+            Action::NewTab => "Open a tab, :tabnew [budget]",
+            Action::CloseTab => "Close the tab",
+            Action::NextTab => "Next tab",
+            Action::PrevTab => "Previous tab",
+            Action::Refresh => "Reload, or retry after an error",
+            Action::Cancel => "Dismiss, or clear the selection",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::PageUp => "Up a page",
+            Action::PageDown => "Down a page",
+            Action::Top => "Go to the first row, a letter twice like gg",
+            Action::Bottom => "Go to the last row",
+            Action::Select => "Open",
+            Action::Filter => "Filter",
+            Action::Find => "Find by name",
+            Action::Toggle => "Toggle the row",
+            Action::SelectNew => "Select the new rows",
+            Action::Create => "Create the selected rows",
+            Action::ToggleSide => "Show or hide the side panel",
+            Action::ToggleDetail => "Show or hide the detail pane",
+            Action::WidenSide => "Widen the side panel",
+            Action::NarrowSide => "Narrow the side panel",
+            Action::GrowDetail => "Grow the detail pane",
+            Action::ShrinkDetail => "Shrink the detail pane",
+            Action::ResetLayout => "Reset the pane sizes",
+            Action::FollowTransfer => "Go to the other side of the transfer",
+            Action::ToggleCleared => "Mark the transaction cleared or uncleared",
+            Action::Approve => "Approve the transaction",
+            Action::Edit => "Edit the date, payee, memo and amount",
+            Action::Delete => "Delete the transaction",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["ctrl-c"],
+            Action::Help => &["?", "ctrl-h"],
+            Action::Command => &[":"],
+            Action::FindBudget => &["B"],
+            Action::Back => &["b", "alt-left"],
+            Action::Forward => &["alt-right", "ctrl-f"],
+            Action::JumpBack => &["ctrl-o"],
+            // Terminals send ctrl-i as tab
+            Action::JumpForward => &["tab"],
+            Action::Breadcrumbs => &["-"],
+            Action::NewTab => &["ctrl-t"],
+            Action::CloseTab => &["ctrl-w"],
+            Action::NextTab => &["]", "ctrl-pagedown"],
+            Action::PrevTab => &["[", "ctrl-pageup"],
+            Action::Refresh => &["r"],
+            Action::Cancel => &["esc"],
+            Action::MoveUp => &["k", "up"],
+            Action::MoveDown => &["j", "down"],
+            Action::MoveLeft => &["h", "left"],
+            Action::MoveRight => &["l", "right"],
+            Action::PageUp => &["pageup", "ctrl-u"],
+            Action::PageDown => &["pagedown", "ctrl-d"],
+            Action::Top => &["g", "home"],
+            Action::Bottom => &["G", "end"],
+            Action::Select => &["enter"],
+            Action::Filter => &["/"],
+            Action::Find => &["f"],
+            Action::Toggle => &["space"],
+            Action::SelectNew => &["a"],
+            Action::Create => &["c"],
+            Action::ToggleSide => &["z"],
+            Action::ToggleDetail => &["p"],
+            Action::WidenSide => &[">"],
+            Action::NarrowSide => &["<"],
+            Action::GrowDetail => &["+"],
+            Action::ShrinkDetail => &["_"],
+            Action::ResetLayout => &["="],
+            Action::FollowTransfer => &["t"],
+            Action::ToggleCleared => &["c"],
+            Action::Approve => &["a"],
+            Action::Edit => &["e"],
+            Action::Delete => &["d", "delete"],
+        }
+    }
+}
+
+/// The keys bound to each action
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Action, Vec<KeyEvent>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|action| {
+                let keys = action.default_keys().iter();
+                let keys = keys.map(|k| parse_key(k).expect("invalid default key"));
+                (*action, keys.collect())
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The defaults, with the actions in `overrides` bound to their keys instead
+    pub fn new(overrides: &HashMap<String, Keys>) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        for (name, Keys(keys)) in overrides {
+            let action = Action::ALL
+                .iter()
+                .find(|a| a.name() == name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = Action::ALL.iter().map(Action::name).collect();
+                    format!(
+                        "Unknown action `{name}` in [keybindings], expected one of {}",
+                        names.join(", ")
+                    )
+                })?;
+            keymap.bindings.insert(*action, keys.clone());
+        }
+        Ok(keymap)
+    }
+
+    /// The first of `actions` that `key` is bound to. Pages pass the actions that make sense in
+    /// their current state, so one key can mean different things in different places.
+    pub fn action(&self, key: &KeyEvent, actions: &[Action]) -> Option<Action> {
+        actions.iter().copied().find(|a| {
+            self.keys(*a)
+                .iter()
+                .any(|bound| normalize(bound) == normalize(key))
+        })
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyEvent] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// A short line like `r: retry   esc: dismiss`, with the first key bound to each action
+    pub fn hints(&self, hints: &[(Action, &str)]) -> String {
+        hints
+            .iter()
+            .filter_map(|(action, label)| {
+                let key = self.keys(*action).first()?;
+                Some(format!("{}: {label}", key_name(key)))
+            })
+            .collect::<Vec<_>>()
+            .join("   ")
+    }
+
+    /// A line per action with its keys and what it does
+    pub fn help(&self, actions: &[Action]) -> String {
+        let lines: Vec<(String, &str)> = actions
+            .iter()
+            .map(|a| {
+                let keys: Vec<String> = self.keys(*a).iter().map(key_name).collect();
+                (keys.join(", "), a.description())
+            })
+            .collect();
+        let width = lines.iter().map(|(keys, _)| keys.len()).max().unwrap_or(0);
+        lines
+            .iter()
+            .map(|(keys, description)| format!("{keys:width$}   {description}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Terminals report `?` or `A` with or without shift, the character already says it
+fn normalize(key: &KeyEvent) -> (KeyCode, KeyModifiers) {
+    match key.code {
+        KeyCode::Char(c) => (KeyCode::Char(c), key.modifiers - KeyModifiers::SHIFT),
+        code => (code, key.modifiers),
+    }
+}
+
+/// The way `key` is written in the config, e.g. `ctrl-h` or `down`
+pub fn key_name(key: &KeyEvent) -> String {
+    let mut name = String::new();
+    for (modifier, prefix) in [
+        (KeyModifiers::CONTROL, "ctrl-"),
+        (KeyModifiers::ALT, "alt-"),
+        (KeyModifiers::SHIFT, "shift-"),
+    ] {
+        if key.modifiers.contains(modifier) {
+            name.push_str(prefix);
+        }
+    }
+    let code = match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{n}"),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        code => format!("{code:?}").to_lowercase(),
+    };
+    name + &code
+}
+
+/// The keymap from the config, or the defaults if there is none, e.g. in tests
+pub fn get() -> &'static Keymap {
+    KEYMAP.get_or_init(|| Keymap::new(&config::get().keybindings).unwrap_or_default())
+}
@@ -0,0 +1,172 @@
+//! How a page splits its area into a side panel, the main pane and a detail pane under it. The
+//! sizes are changed from the keyboard and remembered per page in `layout_file`.
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf, sync::Mutex};
+use tui::layout::*;
+
+use crate::keymap::Action;
+
+/// Below this many columns the side panel goes above the main pane instead of beside it
+pub const NARROW_WIDTH: u16 = 70;
+/// Below this many rows there is no room for the detail pane
+pub const SHORT_HEIGHT: u16 = 16;
+
+const SIDE_RANGE: (u16, u16) = (10, 60);
+const DETAIL_RANGE: (u16, u16) = (15, 70);
+/// How much one key press resizes a pane by, in percent
+const STEP: u16 = 5;
+
+/// `None` until `load` is called. Pages then start with the default layout and keep their
+/// changes to themselves, so tests neither share layouts nor write them.
+static LAYOUTS: Mutex<Option<Layouts>> = Mutex::new(None);
+
+struct Layouts {
+    path: PathBuf,
+    pages: BTreeMap<String, PaneLayout>,
+    /// Set since the last `save`
+    changed: bool,
+}
+
+/// The sizes of a page's panes, in percent of its area
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct PaneLayout {
+    /// Width of the side panel, or its height on narrow terminals
+    pub side: u16,
+    pub side_open: bool,
+    /// Height of the detail pane, taken from the main pane
+    pub detail: u16,
+    pub detail_open: bool,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self {
+            side: 20,
+            side_open: true,
+            detail: 30,
+            detail_open: false,
+        }
+    }
+}
+
+/// The areas a layout gives a page, the panes that do not fit or are closed are `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Panes {
+    pub side: Option<Rect>,
+    pub main: Rect,
+    pub detail: Option<Rect>,
+}
+
+impl PaneLayout {
+    pub fn split(&self, area: Rect) -> Panes {
+        let (side, rest) = match self.side_open {
+            false => (None, area),
+            true if area.width < NARROW_WIDTH => {
+                let (side, rest) = split(Direction::Vertical, self.side, area);
+                (Some(side), rest)
+            }
+            true => {
+                let (side, rest) = split(Direction::Horizontal, self.side, area);
+                (Some(side), rest)
+            }
+        };
+
+        if !self.detail_open || area.height < SHORT_HEIGHT {
+            return Panes {
+                side,
+                main: rest,
+                detail: None,
+            };
+        }
+        let (main, detail) = split(Direction::Vertical, 100 - self.detail, rest);
+        Panes {
+            side,
+            main,
+            detail: Some(detail),
+        }
+    }
+
+    /// Resizes or toggles a pane, returns false for actions that are not about the layout
+    pub fn apply(&mut self, action: Action) -> bool {
+        let (side_min, side_max) = SIDE_RANGE;
+        let (detail_min, detail_max) = DETAIL_RANGE;
+        match action {
+            Action::ToggleSide => self.side_open = !self.side_open,
+            Action::ToggleDetail => self.detail_open = !self.detail_open,
+            Action::WidenSide => self.side = (self.side + STEP).min(side_max),
+            Action::NarrowSide => self.side = self.side.saturating_sub(STEP).max(side_min),
+            Action::GrowDetail => self.detail = (self.detail + STEP).min(detail_max),
+            Action::ShrinkDetail => self.detail = self.detail.saturating_sub(STEP).max(detail_min),
+            Action::ResetLayout => {
+                let default = Self::default();
+                self.side = default.side;
+                self.detail = default.detail;
+            }
+            _ => return false,
+        }
+        // Resizing a closed pane opens it, so the key press is not lost
+        match action {
+            Action::WidenSide | Action::NarrowSide => self.side_open = true,
+            Action::GrowDetail | Action::ShrinkDetail => self.detail_open = true,
+            _ => {}
+        }
+        true
+    }
+}
+
+/// `percent` of `area` for the first part, the rest for the second
+fn split(direction: Direction, percent: u16, area: Rect) -> (Rect, Rect) {
+    let chunks = Layout::default()
+        .constraints([
+            Constraint::Percentage(percent),
+            Constraint::Percentage(100 - percent),
+        ])
+        .direction(direction)
+        .split(area);
+    (chunks[0], chunks[1])
+}
+
+/// Reads the layouts saved in `path`, and saves the ones changed later there. A missing or
+/// unreadable file leaves every page with the default layout.
+pub fn load(path: PathBuf) {
+    let pages = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    *LAYOUTS.lock().unwrap() = Some(Layouts {
+        path,
+        pages,
+        changed: false,
+    });
+}
+
+/// The layout last saved for `page`, or `default` for a page that was never changed
+pub fn get(page: &str, default: PaneLayout) -> PaneLayout {
+    let layouts = LAYOUTS.lock().unwrap();
+    let layout = layouts.as_ref().and_then(|l| l.pages.get(page));
+    layout.copied().unwrap_or(default)
+}
+
+/// Remembers `layout` for `page`, `save` writes it to the file passed to `load`
+pub fn set(page: &str, layout: PaneLayout) {
+    if let Some(layouts) = LAYOUTS.lock().unwrap().as_mut() {
+        layouts.changed |= layouts.pages.insert(page.to_string(), layout) != Some(layout);
+    }
+}
+
+/// Writes every layout to the file passed to `load`, if any changed since the last time
+pub fn save() {
+    if let Some(layouts) = LAYOUTS.lock().unwrap().as_mut() {
+        // Failing to save only means the layout is forgotten when the app quits
+        if std::mem::take(&mut layouts.changed) {
+            let _ = layouts.write();
+        }
+    }
+}
+
+impl Layouts {
+    fn write(&self) -> io::Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.pages)?)
+    }
+}
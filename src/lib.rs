@@ -1,6 +1,17 @@
-#![feature(drain_filter)]
-
+pub mod app;
+pub mod cli;
 pub mod components;
+pub mod config;
 pub mod data_layer;
+pub mod event;
+pub mod export;
+pub mod history;
+pub mod import;
+pub mod keymap;
+pub mod layout;
+#[cfg(feature = "mock")]
+pub mod mock_server;
 pub mod page;
+pub mod terminal;
+pub mod theme;
 pub(crate) mod util;
@@ -1,104 +1,159 @@
-use chrono::Duration;
-use ynab_tui::{data_layer::YnabApi, page::*};
+use clap::Parser;
+use ynab_tui::{
+    app::App,
+    cli::{self, Command},
+    config::{self, Config},
+    data_layer::{BudgetDataSource, FixtureSource, Worker, YnabApi},
+    event::TerminalEvents,
+    layout, terminal,
+};
 
 use crossterm::{event::*, terminal::*, *};
 use std::io;
-use tui::{backend::*, layout::*, widgets::*, *};
+use tui::{backend::*, *};
+
+/// A terminal UI for YNAB
+#[derive(Parser, Default)]
+#[command(version, about)]
+pub struct Args {
+    /// Serve everything from the cache and queue edits until started online again
+    #[arg(long)]
+    offline: bool,
+
+    /// Run against the budgets in a directory of fixture JSON files instead of YNAB
+    #[arg(long, value_name = "DIR")]
+    fixtures: Option<std::path::PathBuf>,
+
+    /// Use the token, cache file and default budget of a profile from the config
+    #[arg(long, short)]
+    profile: Option<String>,
+
+    /// Read the config from this file instead of the default location
+    #[arg(long, value_name = "FILE")]
+    config: Option<std::path::PathBuf>,
+
+    /// Print data instead of starting the UI
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-pub struct App {
-    page_stack: Vec<Box<dyn Page>>,
-    restore_stack: Vec<Box<dyn Page>>,
-    api: YnabApi,
+fn worker(args: &Args, config: &Config) -> std::result::Result<Worker, Box<dyn std::error::Error>> {
+    if let Some(dir) = &args.fixtures {
+        return Ok(Worker::spawn(FixtureSource::load(dir)?));
+    }
+    Ok(Worker::spawn(ynab_api(args, config)?))
 }
 
-#[rustfmt::skip]
-impl Default for App { fn default() -> Self { Self::new() } }
-
-impl App {
-    pub fn new() -> Self {
-        let ynab_token = dotenvy::var("YNAB_TOKEN").expect("YNAB_TOKEN not definded in .env file");
-        let ynab_cache_file =
-            dotenvy::var("YNAB_CACHE_FILE").expect("YNAB_CACHE_FILE not defined in .env file");
-        let mut api = YnabApi::new(&ynab_token, &ynab_cache_file, Duration::hours(1));
-        Self {
-            page_stack: vec![Box::new(Homepage::new(&mut api))],
-            restore_stack: vec![],
-            api,
-        }
+fn ynab_api(
+    args: &Args,
+    config: &Config,
+) -> std::result::Result<YnabApi, Box<dyn std::error::Error>> {
+    let token = config.token()?;
+    let cache_file = config.cache_file()?;
+    let mut api = YnabApi::new(
+        token.expose(),
+        &cache_file.to_string_lossy(),
+        config.refresh.default,
+    );
+    for (resource, duration) in config.refresh.overrides() {
+        api.set_refresh_duration(resource, duration);
     }
+    let token_config = config.clone();
+    api.set_token_source(move || {
+        let token = token_config.refresh_token().map_err(|e| e.to_string())?;
+        Ok(token.expose().to_string())
+    });
+    if let Some(base_url) = &config.base_url {
+        api.set_base_path(base_url);
+    }
+    api.set_offline(args.offline);
+    Ok(api)
+}
 
-    pub fn run(&mut self) -> std::io::Result<()> {
-        let mut terminal = setup_terminal()?;
-
-        loop {
-            let names: Vec<String> = self.page_stack.iter().map(|p| p.name()).collect();
-            let path = names.join(" -> ");
-
-            let Some(page) = self.page_stack.last_mut() else { continue };
-
-            terminal.draw(|f| {
-                let chunks = Layout::default()
-                    .constraints([Constraint::Percentage(10), Constraint::Percentage(90)])
-                    .direction(Direction::Vertical)
-                    .split(f.size());
-                let page_area = chunks[1];
-                let path_area = chunks[0];
-                let path = Paragraph::new(path);
-
-                f.render_widget(path, path_area);
-                page.ui(f, page_area);
-            })?;
-
-            let msg = page.update(&mut self.api)?;
-
-            match msg {
-                Message::Quit => break,
-                Message::Back => {
-                    if self.page_stack.len() > 1 {
-                        self.restore_stack.push(self.page_stack.pop().unwrap());
-                    }
-                }
-                Message::Forward => {
-                    if self.restore_stack.is_empty() {
-                        self.page_stack.push(self.restore_stack.pop().unwrap());
-                    }
-                }
-                Message::NewPage(newpage) => {
-                    self.page_stack.push(newpage);
-                    self.restore_stack.clear();
-                }
-                Message::Noop => {}
-            }
+/// Runs a subcommand against the same source the UI would use, printing to stdout
+fn run_command(
+    args: &Args,
+    config: &Config,
+    command: &Command,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    fn print(
+        command: &Command,
+        mut source: impl BudgetDataSource,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        cli::run(command, &mut source, &mut io::stdout().lock())?;
+        // Scripts should not take cached data for current without being told
+        if let Some(e) = source.take_stale() {
+            eprintln!("Refreshing failed, printed cached data: {e}");
         }
-
-        // restore terminal
-        restore_terminal(terminal)?;
-
         Ok(())
     }
+
+    match &args.fixtures {
+        Some(dir) => print(command, FixtureSource::load(dir)?),
+        None => print(command, ynab_api(args, config)?),
+    }
+}
+
+fn run(worker: Worker, mouse: bool) -> io::Result<()> {
+    let mut events = TerminalEvents::spawn();
+    worker.notify(events.sender());
+    let mut app = App::new(worker);
+    let mut terminal = setup_terminal(mouse)?;
+    let result = app.run(&mut terminal, &mut events);
+
+    // restore terminal, even if the event loop failed
+    terminal::restore(terminal.backend_mut())?;
+
+    result
 }
 
-fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
+fn setup_terminal(mouse: bool) -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, DisableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    } else {
+        execute!(stdout, DisableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
 }
 
-fn restore_terminal(mut terminal: Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-    Ok(())
+/// Leaves raw mode and the alternate screen before a panic message is printed,
+/// otherwise the message is lost and the shell is left unusable
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = terminal::restore(&mut io::stdout());
+        default_hook(info);
+    }));
 }
 
 fn main() -> std::io::Result<()> {
-    dotenvy::dotenv().unwrap();
-    App::new().run()
+    install_panic_hook();
+    let args = Args::parse();
+
+    let config =
+        Config::load(args.config.as_deref(), args.profile.as_deref()).unwrap_or_else(|e| exit(e));
+    config::set(config);
+    let config = config::get();
+
+    if let Some(command) = &args.command {
+        run_command(&args, config, command).unwrap_or_else(|e| exit(e));
+        return Ok(());
+    }
+
+    let worker = worker(&args, config).unwrap_or_else(|e| exit(e));
+    if let Some(path) = config.layout_file() {
+        layout::load(path);
+    }
+
+    run(worker, config.mouse)
+}
+
+fn exit(e: impl std::fmt::Display) -> ! {
+    eprintln!("{e}");
+    std::process::exit(1)
 }
@@ -0,0 +1,447 @@
+//! An in-memory stand-in for the YNAB API, so the bindings and `YnabApi` can be exercised
+//! end to end without a network connection or a real token.
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tiny_http::{Header, Method, Response, Server};
+use ynab_openapi::models::{PostTransactionsWrapper, PutTransactionWrapper, TransactionDetail};
+
+use crate::data_layer::{apply, FixtureBudget};
+
+/// YNAB allows 200 requests per access token within a rolling hour
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+pub struct MockServer {
+    server: Arc<Server>,
+    state: Arc<Mutex<MockState>>,
+    handle: Option<JoinHandle<()>>,
+    addr: SocketAddr,
+}
+
+#[derive(Default)]
+struct MockState {
+    budgets: Vec<FixtureBudget>,
+    server_knowledge: i64,
+    /// The server knowledge at which an entity last changed, by id. Missing means initial data.
+    changed_at: HashMap<String, i64>,
+    token: Option<String>,
+    rate_limit: Option<usize>,
+    recent_requests: VecDeque<Instant>,
+    request_count: usize,
+    fail_next: VecDeque<u16>,
+    created: usize,
+}
+
+impl MockServer {
+    /// Starts serving `budgets` on a free port on localhost
+    pub fn start(budgets: Vec<FixtureBudget>) -> io::Result<Self> {
+        Self::start_on("127.0.0.1:0", budgets)
+    }
+
+    pub fn start_on(addr: &str, budgets: Vec<FixtureBudget>) -> io::Result<Self> {
+        let server = Server::http(addr).map_err(io::Error::other)?;
+        let server = Arc::new(server);
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("not an ip address"))?;
+
+        let state = Arc::new(Mutex::new(MockState {
+            budgets,
+            server_knowledge: 1,
+            ..Default::default()
+        }));
+
+        let handle = {
+            let server = server.clone();
+            let state = state.clone();
+            thread::spawn(move || {
+                for mut request in server.incoming_requests() {
+                    let mut body = String::new();
+                    let _ = request.as_reader().read_to_string(&mut body);
+                    let auth = request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv("Authorization"))
+                        .map(|h| h.value.to_string());
+
+                    let (status, json) = state.lock().unwrap().handle(
+                        request.method(),
+                        request.url(),
+                        auth.as_deref(),
+                        &body,
+                    );
+
+                    let content_type =
+                        Header::from_bytes("Content-Type", "application/json").unwrap();
+                    let response = Response::from_string(json.to_string())
+                        .with_status_code(status)
+                        .with_header(content_type);
+                    let _ = request.respond(response);
+                }
+            })
+        };
+
+        Ok(Self {
+            server,
+            state,
+            handle: Some(handle),
+            addr,
+        })
+    }
+
+    /// The value for `Configuration.base_path`
+    pub fn base_path(&self) -> String {
+        format!("http://{}/v1", self.addr)
+    }
+
+    /// Only accept requests carrying this bearer token, anything else gets a 401
+    pub fn set_token(&self, token: &str) {
+        self.state.lock().unwrap().token = Some(token.to_string());
+    }
+
+    /// Answer with a 429 once more than `requests` were made within the last hour
+    pub fn set_rate_limit(&self, requests: usize) {
+        self.state.lock().unwrap().rate_limit = Some(requests);
+    }
+
+    /// Answer the next request with an error `status`, regardless of what was requested
+    pub fn fail_next(&self, status: u16) {
+        self.state.lock().unwrap().fail_next.push_back(status);
+    }
+
+    /// Number of requests received so far, including rejected ones
+    pub fn request_count(&self) -> usize {
+        self.state.lock().unwrap().request_count
+    }
+
+    pub fn server_knowledge(&self) -> i64 {
+        self.state.lock().unwrap().server_knowledge
+    }
+
+    /// Blocks until the server shuts down
+    pub fn join(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl MockState {
+    fn handle(
+        &mut self,
+        method: &Method,
+        url: &str,
+        auth: Option<&str>,
+        body: &str,
+    ) -> (u16, Value) {
+        self.request_count += 1;
+
+        if let Some(status) = self.fail_next.pop_front() {
+            return error(status);
+        }
+        if let Some(token) = &self.token {
+            if auth != Some(format!("Bearer {token}").as_str()) {
+                return error(401);
+            }
+        }
+        if let Some(limit) = self.rate_limit {
+            let now = Instant::now();
+            while let Some(t) = self.recent_requests.front() {
+                if now.duration_since(*t) < RATE_LIMIT_WINDOW {
+                    break;
+                }
+                self.recent_requests.pop_front();
+            }
+            if self.recent_requests.len() >= limit {
+                return error(429);
+            }
+            self.recent_requests.push_back(now);
+        }
+
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let since = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "last_knowledge_of_server")
+            .and_then(|(_, value)| value.parse::<i64>().ok());
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match (method, segments.as_slice()) {
+            (Method::Get, ["v1", "user"]) => ok(json!({ "user": { "id": "mock-user" } })),
+            (Method::Get, ["v1", "budgets"]) => {
+                let budgets: Vec<_> = self.budgets.iter().map(|b| &b.budget).collect();
+                ok(json!({ "budgets": budgets }))
+            }
+            (_, ["v1", "budgets", budget_id, rest @ ..]) => {
+                let Some(b) = self
+                    .budgets
+                    .iter()
+                    .position(|b| b.budget.id.to_string() == *budget_id)
+                else {
+                    return error(404);
+                };
+                self.handle_budget(method, b, rest, since, body)
+            }
+            _ => error(404),
+        }
+    }
+
+    fn handle_budget(
+        &mut self,
+        method: &Method,
+        b: usize,
+        path: &[&str],
+        since: Option<i64>,
+        body: &str,
+    ) -> (u16, Value) {
+        let knowledge = self.server_knowledge;
+        let budget = &self.budgets[b];
+
+        match (method, path) {
+            (Method::Get, ["accounts"]) => {
+                let accounts =
+                    self.delta(&budget.accounts, |a| a.id.to_string(), |a| a.deleted, since);
+                ok(json!({ "accounts": accounts, "server_knowledge": knowledge }))
+            }
+            (Method::Get, ["transactions"]) => {
+                let transactions =
+                    self.delta(&budget.transactions, |t| t.id.clone(), |t| t.deleted, since);
+                ok(json!({ "transactions": transactions, "server_knowledge": knowledge }))
+            }
+            (Method::Get, ["accounts", account_id, "transactions"]) => {
+                let in_account: Vec<_> = budget
+                    .transactions
+                    .iter()
+                    .filter(|t| t.account_id.to_string() == *account_id)
+                    .cloned()
+                    .collect();
+                let transactions = self.delta(&in_account, |t| t.id.clone(), |t| t.deleted, since);
+                ok(json!({ "transactions": transactions, "server_knowledge": knowledge }))
+            }
+            (Method::Get, ["categories"]) => ok(
+                json!({ "category_groups": budget.category_groups, "server_knowledge": knowledge }),
+            ),
+            (Method::Get, ["payees"]) => {
+                let payees = self.delta(&budget.payees, |p| p.id.to_string(), |p| p.deleted, since);
+                ok(json!({ "payees": payees, "server_knowledge": knowledge }))
+            }
+            (Method::Get, ["months"]) => {
+                let months: Vec<_> = budget
+                    .months
+                    .iter()
+                    .map(|m| {
+                        let mut m = serde_json::to_value(m).unwrap_or_default();
+                        if let Some(m) = m.as_object_mut() {
+                            m.remove("categories");
+                        }
+                        m
+                    })
+                    .collect();
+                ok(json!({ "months": months, "server_knowledge": knowledge }))
+            }
+            (Method::Get, ["months", month]) => {
+                let found = if *month == "current" {
+                    budget.months.iter().max_by(|a, b| a.month.cmp(&b.month))
+                } else {
+                    budget
+                        .months
+                        .iter()
+                        .find(|m| m.month.get(..7) == month.get(..7))
+                };
+                match found {
+                    Some(month) => ok(json!({ "month": month })),
+                    None => error(404),
+                }
+            }
+            (Method::Get, ["transactions", id]) => match self.transaction(b, id) {
+                Some(t) => ok(json!({ "transaction": budget.transactions[t] })),
+                None => error(404),
+            },
+            (Method::Post, ["transactions"]) => self.create_transactions(b, body),
+            (Method::Put, ["transactions", id]) => self.update_transaction(b, id, body),
+            (Method::Delete, ["transactions", id]) => self.delete_transaction(b, id),
+            _ => error(404),
+        }
+    }
+
+    /// Everything changed since `since`, including deletions, or everything not deleted
+    /// when the client has no prior knowledge
+    fn delta<T: Serialize>(
+        &self,
+        items: &[T],
+        id: impl Fn(&T) -> String,
+        deleted: impl Fn(&T) -> bool,
+        since: Option<i64>,
+    ) -> Vec<Value> {
+        items
+            .iter()
+            .filter(|item| match since {
+                Some(since) => self.changed_at.get(&id(item)).copied().unwrap_or(0) > since,
+                None => !deleted(item),
+            })
+            .map(|item| serde_json::to_value(item).unwrap_or_default())
+            .collect()
+    }
+
+    fn transaction(&self, b: usize, id: &str) -> Option<usize> {
+        self.budgets[b]
+            .transactions
+            .iter()
+            .position(|t| t.id == id && !t.deleted)
+    }
+
+    /// Bumps the server knowledge and records that the entities changed at it
+    fn touch(&mut self, ids: &[String]) {
+        self.server_knowledge += 1;
+        for id in ids {
+            self.changed_at.insert(id.clone(), self.server_knowledge);
+        }
+    }
+
+    /// Adds (or with `sign` -1 removes) a transaction's amount to its account's balances
+    fn adjust_balance(&mut self, b: usize, t: &TransactionDetail, sign: i64) -> Option<String> {
+        let account = self.budgets[b]
+            .accounts
+            .iter_mut()
+            .find(|a| a.id == t.account_id)?;
+        account.balance += sign * t.amount;
+        match t.cleared {
+            ynab_openapi::models::transaction_detail::Cleared::Uncleared => {
+                account.uncleared_balance += sign * t.amount
+            }
+            _ => account.cleared_balance += sign * t.amount,
+        }
+        Some(account.id.to_string())
+    }
+
+    fn create_transactions(&mut self, b: usize, body: &str) -> (u16, Value) {
+        let Ok(data) = serde_json::from_str::<PostTransactionsWrapper>(body) else {
+            return error(400);
+        };
+        let bulk = data.transactions.is_some();
+        let saves = match (data.transaction, data.transactions) {
+            (Some(t), None) => vec![*t],
+            (None, Some(ts)) => ts,
+            _ => return error(400),
+        };
+
+        let mut created = vec![];
+        let mut duplicate_import_ids = vec![];
+        for save in saves {
+            let budget = &self.budgets[b];
+            if let Some(import_id) = &save.import_id {
+                if budget
+                    .transactions
+                    .iter()
+                    .any(|t| t.import_id.as_ref() == Some(import_id))
+                {
+                    duplicate_import_ids.push(import_id.clone());
+                    continue;
+                }
+            }
+            if save.account_id.is_none() || save.date.is_none() || save.amount.is_none() {
+                return error(400);
+            }
+
+            self.created += 1;
+            let mut t = TransactionDetail {
+                id: format!("00000000-0000-4000-8000-{:012x}", self.created),
+                ..Default::default()
+            };
+            apply(&mut t, &save, budget);
+            created.push(t);
+        }
+
+        let mut ids: Vec<String> = created.iter().map(|t| t.id.clone()).collect();
+        for t in &created {
+            ids.extend(self.adjust_balance(b, t, 1));
+        }
+        self.touch(&ids);
+        self.budgets[b].transactions.extend(created.iter().cloned());
+
+        let transaction_ids: Vec<&String> = created.iter().map(|t| &t.id).collect();
+        let mut data = json!({
+            "transaction_ids": transaction_ids,
+            "duplicate_import_ids": duplicate_import_ids,
+            "server_knowledge": self.server_knowledge,
+        });
+        if bulk {
+            data["transactions"] = json!(created);
+        } else if let Some(t) = created.first() {
+            data["transaction"] = json!(t);
+        }
+        (201, json!({ "data": data }))
+    }
+
+    fn update_transaction(&mut self, b: usize, id: &str, body: &str) -> (u16, Value) {
+        let Ok(data) = serde_json::from_str::<PutTransactionWrapper>(body) else {
+            return error(400);
+        };
+        let Some(i) = self.transaction(b, id) else {
+            return error(404);
+        };
+
+        let old = self.budgets[b].transactions[i].clone();
+        let mut new = old.clone();
+        apply(&mut new, &data.transaction, &self.budgets[b]);
+
+        let mut ids = vec![new.id.clone()];
+        ids.extend(self.adjust_balance(b, &old, -1));
+        ids.extend(self.adjust_balance(b, &new, 1));
+        self.touch(&ids);
+        self.budgets[b].transactions[i] = new.clone();
+
+        ok(json!({ "transaction": new, "server_knowledge": self.server_knowledge }))
+    }
+
+    fn delete_transaction(&mut self, b: usize, id: &str) -> (u16, Value) {
+        let Some(i) = self.transaction(b, id) else {
+            return error(404);
+        };
+
+        let t = self.budgets[b].transactions[i].clone();
+        let mut ids = vec![t.id.clone()];
+        ids.extend(self.adjust_balance(b, &t, -1));
+        self.touch(&ids);
+
+        let t = &mut self.budgets[b].transactions[i];
+        t.deleted = true;
+        ok(json!({ "transaction": t, "server_knowledge": self.server_knowledge }))
+    }
+}
+
+fn ok(data: Value) -> (u16, Value) {
+    (200, json!({ "data": data }))
+}
+
+/// An error in the shape YNAB sends them
+fn error(status: u16) -> (u16, Value) {
+    let (id, name, detail) = match status {
+        400 => ("400", "bad_request", "Bad request"),
+        401 => ("401", "unauthorized", "Unauthorized"),
+        404 => ("404.2", "resource_not_found", "Resource not found"),
+        429 => ("429", "too_many_requests", "Too many requests"),
+        _ => ("500", "internal_server_error", "Internal server error"),
+    };
+    (
+        status,
+        json!({ "error": { "id": id, "name": name, "detail": detail } }),
+    )
+}
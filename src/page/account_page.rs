@@ -1,165 +1,481 @@
 use super::*;
+use chrono::NaiveDate;
 use crossterm::event::*;
 use ynab_openapi::models::{Account, BudgetSummary, TransactionDetail};
 
-use crate::{components::*, data_layer::*, util::*};
-use std::{io, time::Duration};
+use crate::{
+    components::*,
+    config,
+    data_layer::*,
+    export::Transactions,
+    import,
+    keymap::{self, Action},
+    layout::{self, PaneLayout},
+    util::*,
+};
+use std::{io, path::Path};
 use tui::layout::*;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct AccountsPage {
     budget: BudgetSummary,
     accounts: StatefulList<Account>,
     transactions: StatefulTable<TransactionDetail>,
-    command_pallete: CommandPallete,
+    filter: TextInput,
+    /// The account finder, open in `PageState::FindAccount`
+    finder: Option<FuzzyFinder<usize>>,
+    /// The accounts beside the table and the selected transaction under it
+    layout: PaneLayout,
+    double_click: DoubleClick,
+    /// A letter bound to `top` was pressed once, the second press goes to the top
+    pending_top: bool,
+    /// The account to select once the accounts arrive and the transaction to select once its
+    /// transactions do, after following a transfer
+    jump_to: Option<(Uuid, String)>,
     page_state: PageState,
 }
 
+/// The name the layout is saved under, shared by every accounts page
+const LAYOUT: &str = "accounts";
+
 impl AccountsPage {
-    pub fn new(budget: BudgetSummary, api: &mut YnabApi) -> Self {
-        let account_list = api.get_accounts(&budget.id.to_string()).unwrap();
-        let transactions_list = api.get_transactions(&budget.id.to_string()).unwrap();
+    pub fn new(budget: BudgetSummary, worker: &mut Worker) -> Self {
+        Self::open(budget, None, worker)
+    }
 
+    /// The register of the account, with the transaction selected
+    pub fn at_transaction(
+        budget: BudgetSummary,
+        account_id: Uuid,
+        transaction_id: String,
+        worker: &mut Worker,
+    ) -> Self {
+        Self::open(budget, Some((account_id, transaction_id)), worker)
+    }
+
+    fn open(budget: BudgetSummary, jump_to: Option<(Uuid, String)>, worker: &mut Worker) -> Self {
         let mut accounts = StatefulList::new();
-        accounts
-            .set_items(account_list)
-            .set_title("Accounts")
-            .focus();
+        accounts.set_title("Accounts").set_loading(true).focus();
 
         let mut transactions = StatefulTable::new();
         transactions
-            .set_items(transactions_list)
-            .set_title("Transactions");
+            .set_title("Transactions")
+            .set_columns(config::get().columns())
+            .set_loading(true);
+
+        // The worker answers in order, so the account is selected before its transactions arrive
+        let budget_id = budget.id.to_string();
+        worker.request(Request::Accounts {
+            budget_id: budget_id.clone(),
+        });
+        worker.request(match &jump_to {
+            Some((account_id, _)) => Request::TransactionsByAccount {
+                budget_id,
+                account_id: account_id.to_string(),
+            },
+            None => Request::Transactions { budget_id },
+        });
 
         Self {
             budget,
             accounts,
             transactions,
             page_state: PageState::AccountSelect,
-            command_pallete: Default::default(),
+            filter: TextInput::new(),
+            finder: None,
+            layout: layout::get(LAYOUT, PaneLayout::default()),
+            double_click: DoubleClick::default(),
+            pending_top: false,
+            jump_to,
+        }
+    }
+
+    /// Requests the transactions for the selected account, or for every account if none is selected
+    fn load_transactions(&mut self, worker: &mut Worker) {
+        let budget_id = self.budget.id.to_string();
+        let request = match self.accounts.get_selected() {
+            Some(account) => Request::TransactionsByAccount {
+                budget_id,
+                account_id: account.id.to_string(),
+            },
+            None => Request::Transactions { budget_id },
+        };
+        self.request(worker, request);
+    }
+
+    fn request(&mut self, worker: &mut Worker, request: Request) {
+        self.set_loading(&request, true);
+        worker.request(request);
+    }
+
+    fn set_loading(&mut self, request: &Request, loading: bool) {
+        match request {
+            Request::Budgets
+            | Request::Payees { .. }
+            | Request::Categories { .. }
+            | Request::Save(_) => {}
+            Request::Accounts { .. } => {
+                self.accounts.set_loading(loading);
+            }
+            Request::Transactions { .. } | Request::TransactionsByAccount { .. } => {
+                self.transactions.set_loading(loading);
+            }
         }
     }
 
-    fn edit_command(&mut self, event: Event, prev_state: PageState) -> io::Result<Message> {
+    fn show_error(&mut self, error: DataError, stale: bool, request: Request) {
+        let prev = match &self.page_state {
+            PageState::ErrState { prev, .. } => prev.clone(),
+            state => Box::new(state.clone()),
+        };
+        self.page_state = PageState::ErrState {
+            error,
+            stale,
+            request,
+            prev,
+        };
+    }
+
+    fn handle_error<B: Backend>(
+        &mut self,
+        event: Event,
+        request: Request,
+        prev: PageState,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
         if let Event::Key(key) = event {
-            match key.code {
-                KeyCode::Char(c) => {
-                    self.command_pallete.push(c);
-                }
-                KeyCode::Backspace => {
-                    self.command_pallete.pop();
+            match keymap::get().action(&key, self.page_state.actions()) {
+                Some(Action::Refresh) => {
+                    self.request(worker, request);
+                    self.page_state = prev;
                 }
+                Some(Action::Cancel) => self.page_state = prev,
+                _ => {}
+            }
+        }
+        noop()
+    }
+
+    fn edit_command<B: Backend>(
+        &mut self,
+        event: Event,
+        prev_state: PageState,
+    ) -> io::Result<Message<B>> {
+        if self.filter.handle_event(&event) {
+            return noop();
+        }
+        if let Event::Key(key) = event {
+            match key.code {
                 KeyCode::Enter => {
+                    self.filter.commit();
                     if prev_state == PageState::NavigateTable {
-                        self.transactions.filter(&self.command_pallete)
+                        self.transactions.filter(&self.filter)
                     }
                     self.page_state = prev_state
                 }
+                KeyCode::Esc => self.page_state = prev_state,
                 _ => {}
             }
-            return noop();
         }
         noop()
     }
 
-    fn select_account(&mut self, event: Event, api: &mut YnabApi) -> io::Result<Message> {
+    fn select_account<B: Backend>(
+        &mut self,
+        event: Event,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
         let key = if let Event::Key(key) = event {
             key
         } else {
             return noop();
         };
 
-        match key.code {
-            KeyCode::Char('b') => Ok(Message::Back),
-            KeyCode::Char('r') => {
-                self.accounts
-                    .set_items(api.get_accounts(&self.budget.id.to_string()).unwrap());
+        match keymap::get().action(&key, self.page_state.actions()) {
+            Some(Action::Refresh) => {
+                let budget_id = self.budget.id.to_string();
+                self.request(worker, Request::Accounts { budget_id });
                 noop()
             }
-            KeyCode::Char('k') => {
-                if let Some(account) = self.accounts.select_prev() {
-                    self.transactions.set_items(
-                        api.get_transactions_by_account(
-                            &self.budget.id.to_string(),
-                            &account.id.to_string(),
-                        )
-                        .unwrap(),
-                    );
-                    self.transactions.filter(&self.command_pallete);
+            Some(Action::MoveUp) => {
+                if self.accounts.select_prev().is_some() {
+                    self.load_transactions(worker);
                 }
                 noop()
             }
-            KeyCode::Char('j') => {
-                if let Some(account) = self.accounts.select_next() {
-                    self.transactions.set_items(
-                        api.get_transactions_by_account(
-                            &self.budget.id.to_string(),
-                            &account.id.to_string(),
-                        )
-                        .unwrap(),
-                    );
-                    self.transactions.filter(&self.command_pallete);
+            Some(Action::MoveDown) => {
+                if self.accounts.select_next().is_some() {
+                    self.load_transactions(worker);
                 }
                 noop()
             }
-            KeyCode::Char('/') => {
+            Some(Action::Filter) => {
                 self.switch_to_edit_state();
                 noop()
             }
-            KeyCode::Char('l') => {
-                self.accounts.unfocus();
-                self.transactions.focus();
-                self.page_state = PageState::NavigateTable;
+            Some(Action::Find) => {
+                self.find_account();
+                noop()
+            }
+            Some(Action::MoveRight) => {
+                self.focus_table();
                 noop()
             }
-            KeyCode::Esc => {
+            Some(Action::Cancel) => {
                 self.accounts.unselect();
-                self.transactions
-                    .set_items(api.get_transactions(&self.budget.id.to_string()).unwrap());
+                self.load_transactions(worker);
                 noop()
             }
-            KeyCode::Enter => noop(),
-            _ => noop(),
+            Some(action) => {
+                self.change_layout(action);
+                noop()
+            }
+            None => noop(),
         }
     }
 
-    fn navigate_table(&mut self, event: Event) -> io::Result<Message> {
+    fn navigate_table<B: Backend>(
+        &mut self,
+        event: Event,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
         let key = if let Event::Key(key) = event {
             key
         } else {
             return noop();
         };
 
-        match key.code {
-            KeyCode::Char('j') => {
+        let action = keymap::get().action(&key, self.page_state.actions());
+        let first_press = action == Some(Action::Top)
+            && matches!(key.code, KeyCode::Char(_))
+            && !self.pending_top;
+        self.pending_top = first_press;
+        if first_press {
+            return noop();
+        }
+
+        match action {
+            Some(Action::MoveDown) => {
                 self.transactions.select_next();
                 noop()
             }
-            KeyCode::Char('k') => {
+            Some(Action::MoveUp) => {
                 self.transactions.select_prev();
                 noop()
             }
-            KeyCode::Char('h') => {
+            Some(Action::PageDown) => {
+                self.transactions.page_down();
+                noop()
+            }
+            Some(Action::PageUp) => {
+                self.transactions.page_up();
+                noop()
+            }
+            Some(Action::Top) => {
+                self.transactions.select_first();
+                noop()
+            }
+            Some(Action::Bottom) => {
+                self.transactions.select_last();
+                noop()
+            }
+            Some(Action::MoveLeft) => {
+                if !self.layout.side_open {
+                    self.change_layout(Action::ToggleSide);
+                }
                 self.transactions.unfocus();
                 self.accounts.focus();
                 self.page_state = PageState::AccountSelect;
                 noop()
             }
-            KeyCode::Char('/') => {
-                self.command_pallete.clear();
+            Some(Action::Filter) => {
+                self.filter.clear();
                 self.switch_to_edit_state();
                 noop()
             }
-            KeyCode::Enter => {
-                if let Some(t) = self.transactions.selected() {
-                    return Ok(Message::NewPage(Box::new(TransactionPage::new(t.clone()))));
+            Some(Action::Find) => {
+                self.find_account();
+                noop()
+            }
+            Some(Action::Select) => {
+                if let Some(t) = self.transactions.selected().cloned() {
+                    let page = TransactionPage::new(self.budget.clone(), t, worker);
+                    return Ok(Message::NewPage(Box::new(page)));
                 }
                 noop()
             }
-            _ => noop(),
+            Some(Action::FollowTransfer) => {
+                let transfer = self.transactions.selected().and_then(transfer);
+                if let Some((account_id, transaction_id)) = transfer {
+                    let budget = self.budget.clone();
+                    let page = Self::at_transaction(budget, account_id, transaction_id, worker);
+                    return Ok(Message::NewPage(Box::new(page)));
+                }
+                noop()
+            }
+            Some(action) => {
+                self.change_layout(action);
+                noop()
+            }
+            None => noop(),
         }
     }
 
+    /// Scrolling moves the selection of the list or table under the pointer. A click selects an
+    /// account or a transaction, or sorts by the column, and a double click opens a transaction.
+    fn mouse<B: Backend>(
+        &mut self,
+        mouse: MouseEvent,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
+        let (column, row) = (mouse.column, mouse.row);
+        // The accounts are not drawn while hidden
+        if self.layout.side_open && self.accounts.contains(column, row) {
+            let selected = match mouse.kind {
+                MouseEventKind::ScrollDown => self.accounts.select_next().is_some(),
+                MouseEventKind::ScrollUp => self.accounts.select_prev().is_some(),
+                MouseEventKind::Down(MouseButton::Left) => {
+                    match self.accounts.item_at(column, row) {
+                        Some(i) => self.accounts.select(i).is_some(),
+                        None => false,
+                    }
+                }
+                _ => false,
+            };
+            if selected {
+                self.transactions.unfocus();
+                self.accounts.focus();
+                self.page_state = PageState::AccountSelect;
+                self.load_transactions(worker);
+            }
+        } else if self.transactions.contains(column, row) {
+            match mouse.kind {
+                MouseEventKind::ScrollDown => {
+                    self.transactions.select_next();
+                }
+                MouseEventKind::ScrollUp => {
+                    self.transactions.select_prev();
+                }
+                MouseEventKind::Down(MouseButton::Left) => {
+                    if let Some(column) = self.transactions.header_at(column, row) {
+                        self.transactions.sort_by(column);
+                    } else if let Some(i) = self.transactions.row_at(column, row) {
+                        self.focus_table();
+                        if let Some(t) = self.transactions.select(i).cloned() {
+                            if self.double_click.click(i) {
+                                let page = TransactionPage::new(self.budget.clone(), t, worker);
+                                return Ok(Message::NewPage(Box::new(page)));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        noop()
+    }
+
+    /// `:import file [bank]` previews a statement for the selected account, or the bank's
+    /// `account`
+    fn import<B: Backend>(
+        &mut self,
+        args: &str,
+        worker: &mut Worker,
+    ) -> Result<Message<B>, String> {
+        let (file, bank) = match args.rsplit_once(' ') {
+            Some((file, bank)) if config::get().banks.contains_key(bank) => (file, Some(bank)),
+            _ => (args, None),
+        };
+        if file.is_empty() {
+            return Err("Usage: :import file [bank]".to_string());
+        }
+        let bank = import::bank(bank).map_err(|e| e.to_string())?;
+
+        let account = match (self.accounts.get_selected(), &bank.account) {
+            (Some(account), _) => account.clone(),
+            (None, Some(name)) => self
+                .accounts
+                .items()
+                .iter()
+                .find(|a| a.name.eq_ignore_ascii_case(name))
+                .cloned()
+                .ok_or_else(|| format!("No account `{name}`"))?,
+            (None, None) => return Err("Select the account to import into".to_string()),
+        };
+
+        let statement = import::read(Path::new(file), &bank).map_err(|e| e.to_string())?;
+        let page = ImportPage::new(
+            self.budget.id.to_string(),
+            account,
+            file.to_string(),
+            statement,
+            worker,
+        );
+        Ok(Message::NewPage(Box::new(page)))
+    }
+
+    /// `:date 2024-05-01`, or a month like `2024-05`, selects the transaction nearest to it
+    fn jump_to_date<B: Backend>(&mut self, args: &str) -> Result<Message<B>, String> {
+        let args = args.trim();
+        let date = NaiveDate::parse_from_str(args, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&format!("{args}-01"), "%Y-%m-%d"))
+            .map_err(|_| "Usage: :date YYYY-MM-DD".to_string())?;
+        if self.transactions.select_date(date).is_none() {
+            return Err("No transactions".to_string());
+        }
+        self.focus_table();
+        Ok(Message::Noop)
+    }
+
+    fn find_account(&mut self) {
+        let accounts = self.accounts.items().iter().enumerate();
+        let accounts = accounts.map(|(i, a)| (a.name.clone(), i)).collect();
+        self.finder = Some(FuzzyFinder::new("Accounts", accounts));
+        self.page_state = PageState::FindAccount(Box::new(self.page_state.clone()));
+    }
+
+    fn pick_account<B: Backend>(
+        &mut self,
+        event: Event,
+        prev_state: PageState,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
+        let Some(finder) = &mut self.finder else {
+            self.page_state = prev_state;
+            return noop();
+        };
+        match finder.handle_event(&event) {
+            Pick::Pending => return noop(),
+            Pick::Cancelled => {}
+            Pick::Picked(i) => {
+                self.accounts.select(i);
+                self.load_transactions(worker);
+            }
+        }
+        self.finder = None;
+        self.page_state = prev_state;
+        noop()
+    }
+
+    /// Resizes or toggles a pane and saves the layout. The table takes the focus when the
+    /// accounts are hidden.
+    fn change_layout(&mut self, action: Action) {
+        if !self.layout.apply(action) {
+            return;
+        }
+        layout::set(LAYOUT, self.layout);
+        if !self.layout.side_open && self.page_state == PageState::AccountSelect {
+            self.focus_table();
+        }
+    }
+
+    fn focus_table(&mut self) {
+        self.accounts.unfocus();
+        self.transactions.focus();
+        self.page_state = PageState::NavigateTable;
+    }
+
     fn switch_to_edit_state(&mut self) {
         let prev = Box::new(self.page_state.clone());
         self.page_state = PageState::EditCommand(prev);
@@ -171,8 +487,13 @@ enum PageState {
     AccountSelect,
     EditCommand(Box<PageState>),
     NavigateTable,
-    OverlayHelp,
-    _ErrState(String),
+    FindAccount(Box<PageState>),
+    ErrState {
+        error: DataError,
+        stale: bool,
+        request: Request,
+        prev: Box<PageState>,
+    },
 }
 
 impl PageState {
@@ -182,78 +503,199 @@ impl PageState {
         }
         false
     }
-}
 
-impl Page for AccountsPage {
-    fn ui(&mut self, frame: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect) {
-        let command_pallete = self.command_pallete.ui("Search", self.page_state.is_edit());
+    fn actions(&self) -> &'static [Action] {
+        match self {
+            PageState::AccountSelect => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::MoveRight,
+                Action::Filter,
+                Action::Find,
+                Action::Cancel,
+                Action::Refresh,
+                Action::ToggleSide,
+                Action::ToggleDetail,
+                Action::WidenSide,
+                Action::NarrowSide,
+                Action::GrowDetail,
+                Action::ShrinkDetail,
+                Action::ResetLayout,
+            ],
+            PageState::NavigateTable => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::PageUp,
+                Action::PageDown,
+                Action::Top,
+                Action::Bottom,
+                Action::MoveLeft,
+                Action::Select,
+                Action::FollowTransfer,
+                Action::Filter,
+                Action::Find,
+                Action::ToggleSide,
+                Action::ToggleDetail,
+                Action::WidenSide,
+                Action::NarrowSide,
+                Action::GrowDetail,
+                Action::ShrinkDetail,
+                Action::ResetLayout,
+            ],
+            PageState::EditCommand(_) | PageState::FindAccount(_) => &[],
+            PageState::ErrState { .. } => &[Action::Refresh, Action::Cancel],
+        }
+    }
+}
 
-        if self.command_pallete.is_empty() && !self.page_state.is_edit() {
-            let (master, stack) = master_stack_layout(1, 80, area);
+impl<B: Backend> Page<B> for AccountsPage {
+    fn ui(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let editing = self.page_state.is_edit();
 
-            self.transactions.render(frame, master);
-            self.accounts.render(frame, stack[0]);
+        let panes_area = if self.filter.is_empty() && !editing {
+            area
         } else {
-            let (area, pallete_area) = split_vertical(90, area);
-            let (master, stack) = master_stack_layout(1, 80, area);
-
-            self.transactions.render(frame, master);
-            self.accounts.render(frame, stack[0]);
-            frame.render_widget(command_pallete, pallete_area);
+            let (panes_area, filter_area) = split_vertical(90, area);
+            self.filter.render(frame, filter_area, "Search", editing);
+            panes_area
+        };
+        let panes = self.layout.split(panes_area);
+        self.transactions.render(frame, panes.main);
+        if let Some(side) = panes.side {
+            self.accounts.render(frame, side);
+        }
+        if let Some(detail) = panes.detail {
+            let fields = self.transactions.selected().map(transaction_fields);
+            render_fields(frame, detail, "Transaction", &fields.unwrap_or_default());
         }
 
-        if let PageState::OverlayHelp = self.page_state {
-            let help_text = vec![
-                "?         Open Help",
-                "k         Move Up",
-                "j         Move Down",
-                "l         Move Right",
-                "h         Move Left",
-                "ctrl-c    Quit",
-                "/         Edit Filter Query",
-            ]
-            .join("\n");
-            render_popup_message(30, 70, area, Alignment::Left, &help_text, frame);
+        if let Some(finder) = &self.finder {
+            finder.render(frame, area);
+        }
+        if let PageState::ErrState { error, stale, .. } = &self.page_state {
+            render_error_popup(error, *stale, area, frame)
         }
+    }
 
-        if let PageState::_ErrState(message) = &self.page_state {
-            render_popup_message(30, 30, area, Alignment::Center, message, frame)
+    fn update(&mut self, event: Event, worker: &mut Worker) -> io::Result<Message<B>> {
+        if let Event::Mouse(mouse) = event {
+            if let PageState::AccountSelect | PageState::NavigateTable = self.page_state {
+                return self.mouse(mouse, worker);
+            }
+        }
+        match self.page_state.clone() {
+            PageState::ErrState { request, prev, .. } => {
+                self.handle_error(event, request, *prev, worker)
+            }
+            PageState::EditCommand(prev_state) => self.edit_command(event, *prev_state),
+            PageState::FindAccount(prev_state) => self.pick_account(event, *prev_state, worker),
+            PageState::AccountSelect => self.select_account(event, worker),
+            PageState::NavigateTable => self.navigate_table(event, worker),
         }
     }
 
-    fn update(&mut self, api: &mut YnabApi) -> io::Result<Message> {
-        if let Ok(false) = poll(Duration::from_millis(200)) {
-            return noop();
+    fn on_response(&mut self, response: &Response) -> bool {
+        let budget_id = self.budget.id.to_string();
+        let selected_account = self.accounts.get_selected().map(|a| a.id.to_string());
+
+        // Drop responses meant for another budget, or for an account that is no longer selected
+        let is_current = match &response.request {
+            Request::Accounts { budget_id: b } => *b == budget_id,
+            Request::Transactions { budget_id: b } => *b == budget_id && selected_account.is_none(),
+            Request::TransactionsByAccount {
+                budget_id: b,
+                account_id,
+            } => *b == budget_id && selected_account.as_ref() == Some(account_id),
+            Request::Budgets
+            | Request::Payees { .. }
+            | Request::Categories { .. }
+            | Request::Save(_) => false,
+        };
+        if !is_current {
+            return false;
         }
-        let event = read()?;
 
-        // Global Keybinds, override any PageState Keybinds
-        if let Event::Key(key) = event {
-            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                match key.code {
-                    KeyCode::Char('c') => return Ok(Message::Quit),
-                    KeyCode::Char('h') => {
-                        self.page_state = PageState::OverlayHelp;
-                        return noop();
+        self.set_loading(&response.request, false);
+        match &response.payload {
+            Ok(Payload::Accounts(accounts)) => {
+                self.accounts.set_items(accounts.clone());
+                if let Some((account_id, _)) = &self.jump_to {
+                    match accounts.iter().position(|a| a.id == *account_id) {
+                        Some(i) => {
+                            self.accounts.select(i);
+                            self.focus_table();
+                        }
+                        // Its transactions would be dropped as not current, so stop waiting
+                        None => {
+                            self.jump_to = None;
+                            self.transactions.set_loading(false);
+                            let error = DataError::NotFound("Transfer account".to_string());
+                            self.show_error(error, false, response.request.clone());
+                        }
                     }
-                    _ => (),
                 }
             }
-        }
-
-        match self.page_state.clone() {
-            PageState::_ErrState(_) => {
-                self.page_state = PageState::AccountSelect;
-                noop()
+            Ok(Payload::Transactions(transactions)) => {
+                self.transactions
+                    .set_items(transactions.clone())
+                    .filter(&self.filter);
+                if let Some((_, transaction_id)) = self.jump_to.take() {
+                    let i = self
+                        .transactions
+                        .items()
+                        .iter()
+                        .position(|t| t.id == transaction_id);
+                    if let Some(i) = i {
+                        self.transactions.select(i);
+                    }
+                }
             }
-            PageState::OverlayHelp => {
-                self.page_state = PageState::AccountSelect;
-                noop()
+            Ok(
+                Payload::Budgets(_) | Payload::Payees(_) | Payload::Categories(_) | Payload::Saved,
+            ) => {}
+            Err(e) => {
+                // Without the accounts there is no account to jump to
+                if let Request::Accounts { .. } = response.request {
+                    if self.jump_to.take().is_some() {
+                        self.transactions.set_loading(false);
+                    }
+                }
+                self.show_error(e.clone(), false, response.request.clone())
             }
-            PageState::EditCommand(prev_state) => self.edit_command(event, *prev_state),
-            PageState::AccountSelect => self.select_account(event, api),
-            PageState::NavigateTable => self.navigate_table(event),
         }
+
+        if let Some(e) = &response.stale {
+            self.show_error(e.clone(), true, response.request.clone());
+        }
+        true
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        self.page_state.actions()
+    }
+
+    fn captures_input(&self) -> bool {
+        self.page_state.is_edit() || matches!(self.page_state, PageState::FindAccount(_))
+    }
+
+    fn command(
+        &mut self,
+        name: &str,
+        args: &str,
+        worker: &mut Worker,
+    ) -> Option<Result<Message<B>, String>> {
+        match name {
+            "import" => Some(self.import(args, worker)),
+            "date" => Some(self.jump_to_date(args)),
+            _ => None,
+        }
+    }
+
+    fn transactions(&self) -> Option<Transactions<'_>> {
+        Some(Transactions::new(
+            self.accounts.items(),
+            self.transactions.items(),
+        ))
     }
 
     fn name(&self) -> String {
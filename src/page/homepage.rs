@@ -2,91 +2,210 @@ use super::*;
 use crossterm::event::*;
 use ynab_openapi::models::BudgetSummary;
 
-use crate::{components::*, data_layer::*};
-use std::{io, time::Duration};
+use crate::{
+    components::*,
+    config,
+    data_layer::*,
+    keymap::{self, Action},
+};
+use std::io;
 use tui::layout::*;
 
 pub struct Homepage {
     budgets: StatefulList<BudgetSummary>,
+    double_click: DoubleClick,
+    page_state: PageState,
 }
 
 impl Homepage {
-    pub fn new(api: &mut YnabApi) -> Self {
+    pub fn new(worker: &mut Worker) -> Self {
         let mut budgets = StatefulList::new();
-        budgets
-            .set_items(api.get_budgets().unwrap())
-            .set_title("Budgets")
-            .focus()
-            .select_next();
+        budgets.set_title("Budgets").set_loading(true).focus();
+        worker.request(Request::Budgets);
 
-        Self { budgets }
+        Self {
+            budgets,
+            double_click: DoubleClick::default(),
+            page_state: PageState::BudgetSelect,
+        }
     }
 
-    fn select_budget(&mut self, event: Event, api: &mut YnabApi) -> io::Result<Message> {
+    fn select_budget<B: Backend>(
+        &mut self,
+        event: Event,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
+        if let Event::Mouse(mouse) = event {
+            return self.mouse(mouse, worker);
+        }
         #[rustfmt::skip]
         let key = if let Event::Key(key) = event { key } else { return noop(); };
 
-        match key.code {
-            KeyCode::Char('r') => {
-                self.budgets.set_items(api.get_budgets().unwrap());
+        match keymap::get().action(&key, self.page_state.actions()) {
+            Some(Action::Refresh) => {
+                self.budgets.set_loading(true);
+                worker.request(Request::Budgets);
                 noop()
             }
-            KeyCode::Char('k') => {
+            Some(Action::MoveUp) => {
                 self.budgets.select_prev();
                 noop()
             }
-            KeyCode::Char('j') => {
+            Some(Action::MoveDown) => {
                 self.budgets.select_next();
                 noop()
             }
-            KeyCode::Enter => {
-                if let Some(budget) = self.budgets.get_selected() {
-                    return Ok(Message::NewPage(Box::new(AccountsPage::new(
-                        budget.clone(),
-                        api,
-                    ))));
-                };
-                noop()
-            }
+            Some(Action::Select) => self.open_budget(worker),
             _ => noop(),
         }
     }
+
+    /// Scrolling moves the selection, a click selects a budget and a double click opens it
+    fn mouse<B: Backend>(
+        &mut self,
+        mouse: MouseEvent,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
+        let (column, row) = (mouse.column, mouse.row);
+        if !self.budgets.contains(column, row) {
+            return noop();
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                self.budgets.select_next();
+            }
+            MouseEventKind::ScrollUp => {
+                self.budgets.select_prev();
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(i) = self.budgets.item_at(column, row) {
+                    self.budgets.select(i);
+                    if self.double_click.click(i) {
+                        return self.open_budget(worker);
+                    }
+                }
+            }
+            _ => {}
+        }
+        noop()
+    }
+
+    fn open_budget<B: Backend>(&mut self, worker: &mut Worker) -> io::Result<Message<B>> {
+        match self.budgets.get_selected() {
+            Some(budget) => Ok(Message::NewPage(Box::new(AccountsPage::new(
+                budget.clone(),
+                worker,
+            )))),
+            None => noop(),
+        }
+    }
+
+    /// Selects `default_budget` from the config, or the first budget
+    fn select_default_budget(&mut self) {
+        let default = config::get().default_budget.as_deref();
+        let index = self
+            .budgets
+            .items()
+            .iter()
+            .position(|b| default.is_some_and(|d| b.name == d || b.id.to_string() == d));
+        match index {
+            Some(i) => self.budgets.select(i),
+            None => self.budgets.select_next(),
+        };
+    }
+
+    fn handle_error<B: Backend>(
+        &mut self,
+        event: Event,
+        worker: &mut Worker,
+    ) -> io::Result<Message<B>> {
+        if let Event::Key(key) = event {
+            match keymap::get().action(&key, self.page_state.actions()) {
+                Some(Action::Refresh) => {
+                    self.budgets.set_loading(true);
+                    worker.request(Request::Budgets);
+                    self.page_state = PageState::BudgetSelect;
+                }
+                Some(Action::Cancel) => self.page_state = PageState::BudgetSelect,
+                _ => {}
+            }
+        }
+        noop()
+    }
 }
 
 #[derive(PartialEq)]
 enum PageState {
-    _BudgetSelect,
+    BudgetSelect,
+    ErrState { error: DataError, stale: bool },
 }
 
-impl Page for Homepage {
-    fn ui(&mut self, frame: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect) {
+impl PageState {
+    fn actions(&self) -> &'static [Action] {
+        match self {
+            PageState::BudgetSelect => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::Select,
+                Action::Refresh,
+            ],
+            PageState::ErrState { .. } => &[Action::Refresh, Action::Cancel],
+        }
+    }
+}
+
+impl<B: Backend> Page<B> for Homepage {
+    fn ui(&mut self, frame: &mut Frame<B>, area: Rect) {
         self.budgets.render(frame, area);
+
+        if let PageState::ErrState { error, stale } = &self.page_state {
+            render_error_popup(error, *stale, area, frame)
+        }
     }
 
-    fn update(&mut self, api: &mut YnabApi) -> io::Result<Message> {
-        if let Ok(false) = poll(Duration::from_millis(200)) {
-            return noop();
+    fn update(&mut self, event: Event, worker: &mut Worker) -> io::Result<Message<B>> {
+        match self.page_state {
+            PageState::BudgetSelect => self.select_budget(event, worker),
+            PageState::ErrState { .. } => self.handle_error(event, worker),
         }
-        let event = read()?;
+    }
 
-        // Global Keybinds, override any PageState Keybinds
-        if let Event::Key(key) = event {
-            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                match key.code {
-                    KeyCode::Char('c') => return Ok(Message::Quit),
-                    _ => (),
+    fn on_response(&mut self, response: &Response) -> bool {
+        if response.request != Request::Budgets {
+            return false;
+        }
+
+        self.budgets.set_loading(false);
+        match &response.payload {
+            Ok(Payload::Budgets(budgets)) => {
+                self.budgets.set_items(budgets.clone());
+                if self.budgets.get_selected().is_none() {
+                    self.select_default_budget();
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.page_state = PageState::ErrState {
+                    error: e.clone(),
+                    stale: false,
                 }
             }
         }
 
-        self.select_budget(event, api)
+        if let Some(e) = &response.stale {
+            self.page_state = PageState::ErrState {
+                error: e.clone(),
+                stale: true,
+            };
+        }
+        true
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        self.page_state.actions()
     }
 
     fn name(&self) -> String {
         String::from("Homepage")
     }
 }
-
-fn noop() -> io::Result<Message> {
-    Ok(Message::Noop)
-}
@@ -0,0 +1,329 @@
+use crossterm::event::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+};
+use tui::{layout::*, widgets::*};
+use ynab_openapi::models::{Account, TransactionDetail};
+
+use super::*;
+use crate::{
+    components::*,
+    data_layer::*,
+    import::{self, Duplicate, ImportRow, StatementRow},
+    keymap::{self, Action},
+    layout::{self, PaneLayout},
+    theme,
+    util::{plain_amount, split_vertical},
+};
+
+/// The name the layout is saved under
+const LAYOUT: &str = "import";
+
+/// Previews a bank statement before its new rows are created in an account
+pub struct ImportPage {
+    budget_id: String,
+    account: Account,
+    file: String,
+    statement: Vec<StatementRow>,
+    rows: Vec<ImportRow>,
+    state: TableState,
+    /// The selected row in full under the table, there is no side panel
+    layout: PaneLayout,
+    /// What this page asked the worker for and has no answer to yet, oldest first. The worker
+    /// answers in order, so only a response to the first one is this page's.
+    requested: VecDeque<Request>,
+    /// How many transactions the last import created
+    created: Option<usize>,
+    error: Option<(DataError, Request)>,
+}
+
+impl ImportPage {
+    pub fn new(
+        budget_id: String,
+        account: Account,
+        file: String,
+        statement: Vec<StatementRow>,
+        worker: &mut Worker,
+    ) -> Self {
+        let mut page = Self {
+            budget_id,
+            account,
+            file,
+            rows: import::preview(statement.clone(), &[]),
+            statement,
+            state: TableState::default(),
+            layout: layout::get(
+                LAYOUT,
+                PaneLayout {
+                    side_open: false,
+                    ..PaneLayout::default()
+                },
+            ),
+            requested: VecDeque::new(),
+            created: None,
+            error: None,
+        };
+        page.load_transactions(worker);
+        page
+    }
+
+    /// The account's transactions, to tell which rows are new
+    fn load_transactions(&mut self, worker: &mut Worker) {
+        self.request(
+            worker,
+            Request::TransactionsByAccount {
+                budget_id: self.budget_id.clone(),
+                account_id: self.account.id.to_string(),
+            },
+        );
+    }
+
+    fn request(&mut self, worker: &mut Worker, request: Request) {
+        self.requested.push_back(request.clone());
+        worker.request(request);
+    }
+
+    fn loading(&self) -> bool {
+        !self.requested.is_empty()
+    }
+
+    fn create(&mut self, worker: &mut Worker) {
+        let transactions = import::save_transactions(&self.rows, self.account.id);
+        if transactions.is_empty() {
+            return;
+        }
+        let edit = Edit::Import {
+            budget_id: self.budget_id.clone(),
+            transactions,
+        };
+        self.save(edit, worker);
+    }
+
+    fn save(&mut self, edit: Edit, worker: &mut Worker) {
+        if let Edit::Import { transactions, .. } = &edit {
+            self.created = Some(transactions.len());
+        }
+        self.request(worker, Request::Save(Box::new(edit)));
+        // Answered after the save, so the rows just created show as imported
+        self.load_transactions(worker);
+    }
+
+    /// Checks the statement against `transactions` again. Rows that are still new, imported or
+    /// similar as before keep the selection they had.
+    fn set_transactions(&mut self, transactions: &[TransactionDetail]) {
+        let before: HashMap<String, (Option<Duplicate>, bool)> = self
+            .rows
+            .drain(..)
+            .map(|r| (r.import_id, (r.duplicate, r.selected)))
+            .collect();
+        self.rows = import::preview(self.statement.clone(), transactions);
+        for row in &mut self.rows {
+            if let Some((duplicate, selected)) = before.get(&row.import_id) {
+                if *duplicate == row.duplicate {
+                    row.selected = *selected;
+                }
+            }
+        }
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let len = self.rows.len() as isize;
+        let i = match self.state.selected() {
+            Some(i) => (i as isize + offset).rem_euclid(len),
+            None => 0,
+        };
+        self.state.select(Some(i as usize));
+    }
+
+    fn title(&self) -> String {
+        let selected = self.rows.iter().filter(|r| r.selected).count();
+        let mut title = format!(
+            "Import {} into {}: {selected} of {} selected",
+            self.file,
+            self.account.name,
+            self.rows.len()
+        );
+        if let Some(created) = self.created.filter(|_| !self.loading()) {
+            title = format!("{title}, created {created}");
+        }
+        title
+    }
+
+    fn change_layout(&mut self, action: Action) {
+        if self.layout.apply(action) {
+            layout::set(LAYOUT, self.layout);
+        }
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        let Some(r) = self.state.selected().and_then(|i| self.rows.get(i)) else {
+            return vec![];
+        };
+        vec![
+            ("Date", r.row.date.to_string()),
+            ("Payee", r.row.payee.clone()),
+            ("Memo", r.row.memo.clone().unwrap_or_default()),
+            ("Amount", plain_amount(r.row.amount)),
+            ("Status", status(r)),
+            (
+                "Selected",
+                if r.selected { "yes" } else { "no" }.to_string(),
+            ),
+        ]
+    }
+}
+
+impl<B: Backend> Page<B> for ImportPage {
+    fn ui(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let rows = self.rows.iter().map(|r| {
+            let theme = theme::get();
+            let style = match r.duplicate {
+                None => theme.text,
+                Some(_) => theme.dim,
+            };
+            Row::new(vec![
+                Cell::from(if r.selected { "[x]" } else { "[ ]" }),
+                r.row.date.to_string().into(),
+                r.row.payee.clone().into(),
+                r.row.memo.clone().unwrap_or_default().into(),
+                Cell::from(plain_amount(r.row.amount)).style(theme.amount(r.row.amount)),
+                status(r).into(),
+            ])
+            .style(style)
+        });
+
+        let (panes_area, help_area) = split_vertical(90, area);
+        let panes = self.layout.split(panes_area);
+        let table = Table::new(rows)
+            .header(Row::new(vec![
+                "", "Date", "Payee", "Memo", "Amount", "Status",
+            ]))
+            .block(active_block().title(title(&self.title(), self.loading())))
+            .highlight_style(theme::get().selected_row)
+            .widths(&[
+                Constraint::Length(3),
+                Constraint::Length(10),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Length(10),
+                Constraint::Percentage(30),
+            ]);
+        frame.render_stateful_widget(table, panes.main, &mut self.state);
+        if let Some(detail) = panes.detail {
+            render_fields(frame, detail, "Row", &self.details());
+        }
+
+        let help = keymap::get().hints(&[
+            (Action::Toggle, "toggle"),
+            (Action::SelectNew, "select new"),
+            (Action::Create, "create selected"),
+            (Action::Back, "back"),
+        ]);
+        let help = Paragraph::new(help).block(block());
+        frame.render_widget(help, help_area);
+
+        if let Some((error, _)) = &self.error {
+            render_error_popup(error, false, area, frame);
+        }
+    }
+
+    fn update(&mut self, event: Event, worker: &mut Worker) -> io::Result<Message<B>> {
+        let Event::Key(key) = event else {
+            return noop();
+        };
+
+        let action = keymap::get().action(&key, Page::<B>::actions(self));
+        if let Some((_, request)) = self.error.clone() {
+            match action {
+                Some(Action::Refresh) => {
+                    self.error = None;
+                    match request {
+                        Request::Save(edit) => self.save(*edit, worker),
+                        request => self.request(worker, request),
+                    }
+                }
+                Some(Action::Cancel) => self.error = None,
+                _ => {}
+            }
+            return noop();
+        }
+
+        match action {
+            Some(Action::Cancel) => return Ok(Message::Back),
+            Some(Action::MoveDown) => self.select(1),
+            Some(Action::MoveUp) => self.select(-1),
+            Some(Action::Toggle) => {
+                if let Some(row) = self.state.selected().and_then(|i| self.rows.get_mut(i)) {
+                    row.selected = !row.selected;
+                }
+            }
+            Some(Action::SelectNew) => {
+                for row in &mut self.rows {
+                    row.selected = row.duplicate.is_none();
+                }
+            }
+            Some(Action::Create) if !self.loading() => self.create(worker),
+            Some(action) => self.change_layout(action),
+            None => {}
+        }
+        noop()
+    }
+
+    fn on_response(&mut self, response: &Response) -> bool {
+        if self.requested.front() != Some(&response.request) {
+            return false;
+        }
+        self.requested.pop_front();
+
+        match &response.payload {
+            Ok(Payload::Transactions(transactions)) => {
+                let selected = self.state.selected();
+                self.set_transactions(transactions);
+                self.state.select(selected.filter(|&i| i < self.rows.len()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if let Request::Save(_) = response.request {
+                    self.created = None;
+                }
+                self.error = Some((e.clone(), response.request.clone()));
+            }
+        }
+        true
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        match self.error {
+            Some(_) => &[Action::Refresh, Action::Cancel],
+            None => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::Toggle,
+                Action::SelectNew,
+                Action::Create,
+                Action::Cancel,
+                Action::ToggleDetail,
+                Action::GrowDetail,
+                Action::ShrinkDetail,
+                Action::ResetLayout,
+            ],
+        }
+    }
+
+    fn name(&self) -> String {
+        String::from("Import")
+    }
+}
+
+/// Whether the row is new, or why it is left out
+fn status(r: &ImportRow) -> String {
+    match &r.duplicate {
+        None => "new".to_string(),
+        Some(Duplicate::Imported) => "imported".to_string(),
+        Some(Duplicate::Similar { date, payee }) => format!("like {date} {payee}"),
+    }
+}
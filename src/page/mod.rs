@@ -1,30 +1,71 @@
 mod account_page;
 mod homepage;
+mod import_page;
 mod transaction_page;
 pub use account_page::*;
 pub use homepage::*;
+pub use import_page::*;
 pub use transaction_page::*;
 
-use crate::data_layer::YnabApi;
+use crate::{
+    data_layer::{Response, Worker},
+    export::Transactions,
+    keymap::Action,
+};
+use crossterm::event::Event;
 use std::io;
-use tui::{backend::CrosstermBackend, layout::Rect, Frame};
+use tui::{backend::Backend, layout::Rect, Frame};
 
-pub enum Message {
+pub enum Message<B: Backend> {
     Back,
     Quit,
-    NewPage(Box<dyn Page>),
+    NewPage(Box<dyn Page<B>>),
     Noop,
     Forward,
 }
 
-pub trait Page {
-    fn ui(&mut self, frame: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect);
+/// A screen of the app. Generic over the backend so pages can be rendered into a
+/// `TestBackend` as well as the terminal.
+pub trait Page<B: Backend> {
+    fn ui(&mut self, frame: &mut Frame<B>, area: Rect);
 
-    fn update(&mut self, api: &mut YnabApi) -> io::Result<Message>;
+    fn update(&mut self, event: Event, worker: &mut Worker) -> io::Result<Message<B>>;
+
+    /// Called with every response the worker finishes, pages ignore the ones they did not ask for.
+    /// Returns whether the page changed and has to be drawn again.
+    fn on_response(&mut self, _response: &Response) -> bool {
+        false
+    }
 
     fn name(&self) -> String;
+
+    /// The actions the page handles in its current state, listed in the help popup
+    fn actions(&self) -> &'static [Action] {
+        &[]
+    }
+
+    /// Whether the page is reading text, so that keys like `:` have to reach it
+    fn captures_input(&self) -> bool {
+        false
+    }
+
+    /// Runs a `:` command the app does not know itself, `None` if the page does not know it
+    /// either
+    fn command(
+        &mut self,
+        _name: &str,
+        _args: &str,
+        _worker: &mut Worker,
+    ) -> Option<Result<Message<B>, String>> {
+        None
+    }
+
+    /// The transactions on screen, as filtered, for `:export`
+    fn transactions(&self) -> Option<Transactions<'_>> {
+        None
+    }
 }
 
-pub fn noop() -> io::Result<Message> {
+pub fn noop<B: Backend>() -> io::Result<Message<B>> {
     Ok(Message::Noop)
 }
@@ -1,55 +1,642 @@
-use std::{io, time::Duration};
+use std::io;
 
-use crossterm::event::{poll, read, Event, KeyCode};
-use tui::{backend::CrosstermBackend, layout::Rect, Frame};
-use ynab_openapi::models::TransactionDetail;
+use chrono::NaiveDate;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    widgets::{Clear, Paragraph},
+};
+use uuid::Uuid;
+use ynab_openapi::models::{
+    transaction_detail::Cleared, BudgetSummary, CategoryGroupWithCategories, Payee,
+    SaveTransaction, TransactionDetail,
+};
 
-use crate::{components::StatefulList, data_layer::YnabApi};
+use crate::{
+    components::*,
+    data_layer::{convert::cleared_to_save, DataError, Edit, Payload, Request, Response, Worker},
+    import::parse_amount,
+    keymap::{self, Action},
+    util::*,
+};
 
 use super::*;
 
 pub struct TransactionPage {
-    transaction: StatefulList<String>,
+    budget: BudgetSummary,
+    transaction: TransactionDetail,
+    /// The other side of a transfer, loaded from its account's transactions
+    transfer: Option<(Uuid, String)>,
+    /// `None` while the other side loads, the reason it is missing if it could not be found
+    other_side: Option<Result<TransactionDetail, String>>,
+    /// An edit was sent and the transaction is reloading
+    saving: bool,
+    deleted: bool,
+    /// Why the last quick action did nothing, shown until the next key
+    note: Option<String>,
+    /// What the edit form's pickers offer, loaded when it first opens
+    payees: Option<Vec<Payee>>,
+    categories: Option<Vec<CategoryGroupWithCategories>>,
+    /// The payee or category picker over the edit form
+    finder: Option<FuzzyFinder<Uuid>>,
+    state: State,
 }
 
+enum State {
+    View,
+    /// The edit form, `field` indexes `EDIT_FIELDS`. The payee and category inputs show what
+    /// was picked for them, `payee_id` is `None` for a payee that does not exist yet.
+    Edit {
+        inputs: Vec<TextInput>,
+        field: usize,
+        payee_id: Option<Uuid>,
+        category_id: Option<Uuid>,
+        error: Option<String>,
+    },
+    ConfirmDelete,
+    Error(DataError, Request),
+}
+
+const EDIT_FIELDS: [&str; 5] = ["Date", "Payee", "Category", "Memo", "Amount"];
+const PAYEE: usize = 1;
+const CATEGORY: usize = 2;
+
 impl TransactionPage {
-    pub fn new(t: TransactionDetail) -> Self {
-        let mut transaction = StatefulList::new();
-        let xs = vec![
-            t.date,
-            t.account_name,
-            t.payee_name.unwrap_or_default(),
-            t.category_name.unwrap_or_default(),
-            t.memo.unwrap_or_default(),
-            t.amount.to_string(),
+    pub fn new(budget: BudgetSummary, t: TransactionDetail, worker: &mut Worker) -> Self {
+        let transfer = transfer(&t);
+        if let Some((account_id, _)) = &transfer {
+            worker.request(Request::TransactionsByAccount {
+                budget_id: budget.id.to_string(),
+                account_id: account_id.to_string(),
+            });
+        }
+        Self {
+            budget,
+            transaction: t,
+            transfer,
+            other_side: None,
+            saving: false,
+            deleted: false,
+            note: None,
+            payees: None,
+            categories: None,
+            finder: None,
+            state: State::View,
+        }
+    }
+
+    fn update_transaction(&mut self, transaction: SaveTransaction, worker: &mut Worker) {
+        self.save(
+            Edit::Update {
+                budget_id: self.budget.id.to_string(),
+                original: self.transaction.clone(),
+                transaction,
+            },
+            worker,
+        );
+    }
+
+    fn save(&mut self, edit: Edit, worker: &mut Worker) {
+        self.saving = true;
+        worker.request(Request::Save(Box::new(edit)));
+        // Answered after the save, for this page and for the register it was opened from
+        let budget_id = self.budget.id.to_string();
+        worker.request(Request::TransactionsByAccount {
+            budget_id: budget_id.clone(),
+            account_id: self.transaction.account_id.to_string(),
+        });
+        worker.request(Request::Transactions { budget_id });
+    }
+
+    fn toggle_cleared(&mut self, worker: &mut Worker) {
+        let cleared = match self.transaction.cleared {
+            Cleared::Uncleared => Cleared::Cleared,
+            Cleared::Cleared => Cleared::Uncleared,
+            Cleared::Reconciled => {
+                self.note = Some("Reconciled transactions stay cleared".to_string());
+                return;
+            }
+        };
+        let transaction = SaveTransaction {
+            cleared: Some(cleared_to_save(cleared)),
+            ..SaveTransaction::new()
+        };
+        self.update_transaction(transaction, worker);
+    }
+
+    fn approve(&mut self, worker: &mut Worker) {
+        if self.transaction.approved {
+            self.note = Some("Already approved".to_string());
+            return;
+        }
+        let transaction = SaveTransaction {
+            approved: Some(true),
+            ..SaveTransaction::new()
+        };
+        self.update_transaction(transaction, worker);
+    }
+
+    fn start_edit(&mut self, worker: &mut Worker) {
+        let budget_id = self.budget.id.to_string();
+        if self.payees.is_none() {
+            let budget_id = budget_id.clone();
+            worker.request(Request::Payees { budget_id });
+        }
+        if self.categories.is_none() {
+            worker.request(Request::Categories { budget_id });
+        }
+
+        let t = &self.transaction;
+        let values = [
+            t.date.clone(),
+            t.payee_name.clone().unwrap_or_default(),
+            t.category_name.clone().unwrap_or_default(),
+            t.memo.clone().unwrap_or_default(),
+            plain_amount(t.amount),
         ];
-        transaction.set_items(xs);
-        Self { transaction }
+        let inputs = values
+            .iter()
+            .map(|value| {
+                let mut input = TextInput::new();
+                input.set(value);
+                input
+            })
+            .collect();
+        self.state = State::Edit {
+            inputs,
+            field: 0,
+            payee_id: t.payee_id,
+            category_id: t.category_id,
+            error: None,
+        };
+    }
+
+    /// A finder over the payees or categories for `field`, `None` until they have loaded
+    fn picker(&self, field: usize) -> Option<FuzzyFinder<Uuid>> {
+        let items = match field {
+            PAYEE => self
+                .payees
+                .as_ref()?
+                .iter()
+                .filter(|p| !p.deleted)
+                .map(|p| (p.name.clone(), p.id))
+                .collect(),
+            _ => self
+                .categories
+                .as_ref()?
+                .iter()
+                .filter(|g| !g.deleted && !g.hidden)
+                .flat_map(|g| {
+                    g.categories
+                        .iter()
+                        .filter(|c| !c.deleted && !c.hidden)
+                        .map(|c| (format!("{}: {}", g.name, c.name), c.id))
+                })
+                .collect(),
+        };
+        Some(FuzzyFinder::new(EDIT_FIELDS[field], items))
+    }
+
+    /// The name of the payee or category `id` picked for `field`
+    fn picked_name(&self, field: usize, id: Uuid) -> String {
+        let name = match field {
+            PAYEE => self
+                .payees
+                .iter()
+                .flatten()
+                .find(|p| p.id == id)
+                .map(|p| p.name.clone()),
+            _ => self
+                .categories
+                .iter()
+                .flatten()
+                .flat_map(|g| &g.categories)
+                .find(|c| c.id == id)
+                .map(|c| c.name.clone()),
+        };
+        name.unwrap_or_default()
+    }
+
+    /// Keys for an open picker. Enter with nothing matching names a new payee.
+    fn pick(&mut self, event: Event) {
+        let (State::Edit { field, .. }, Some(finder)) = (&self.state, &mut self.finder) else {
+            return;
+        };
+        let field = *field;
+        let enter = matches!(&event, Event::Key(key) if key.code == KeyCode::Enter);
+        let picked = if enter && field == PAYEE && finder.matches().is_empty() {
+            let name = finder.query().trim().to_string();
+            if name.is_empty() {
+                return;
+            }
+            Some((name, None))
+        } else {
+            match finder.handle_event(&event) {
+                Pick::Pending => return,
+                Pick::Cancelled => None,
+                Pick::Picked(id) => Some((self.picked_name(field, id), Some(id))),
+            }
+        };
+
+        self.finder = None;
+        let State::Edit {
+            inputs,
+            payee_id,
+            category_id,
+            ..
+        } = &mut self.state
+        else {
+            return;
+        };
+        if let Some((name, id)) = picked {
+            inputs[field].set(&name);
+            match field {
+                PAYEE => *payee_id = id,
+                _ => *category_id = id,
+            }
+        }
+    }
+
+    fn edit(&mut self, event: Event, worker: &mut Worker) {
+        if self.finder.is_some() {
+            return self.pick(event);
+        }
+        let State::Edit {
+            inputs,
+            field,
+            payee_id,
+            category_id,
+            error,
+            ..
+        } = &mut self.state
+        else {
+            return;
+        };
+        // The payee and category are picked rather than typed
+        let on_picker = matches!(*field, PAYEE | CATEGORY);
+        if !on_picker && inputs[*field].handle_event(&event) {
+            return;
+        }
+        let Event::Key(key) = event else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter | KeyCode::Char(_) if on_picker => {
+                let field = *field;
+                self.open_picker(field, key);
+            }
+            KeyCode::Tab => *field = (*field + 1) % EDIT_FIELDS.len(),
+            KeyCode::BackTab => *field = (*field + EDIT_FIELDS.len() - 1) % EDIT_FIELDS.len(),
+            KeyCode::Esc => self.state = State::View,
+            KeyCode::Enter => match changes(&self.transaction, inputs, *payee_id, *category_id) {
+                Ok(None) => self.state = State::View,
+                Ok(Some(transaction)) => {
+                    self.state = State::View;
+                    self.update_transaction(transaction, worker);
+                }
+                Err(e) => *error = Some(e),
+            },
+            _ => {}
+        }
+    }
+
+    /// Opens the finder for `field`, a typed character starts its search
+    fn open_picker(&mut self, field: usize, key: KeyEvent) {
+        let picker = self.picker(field);
+        let State::Edit { error, .. } = &mut self.state else {
+            return;
+        };
+        let Some(mut picker) = picker else {
+            *error = Some("Payees and categories are still loading".to_string());
+            return;
+        };
+        if let KeyCode::Char(_) = key.code {
+            picker.handle_event(&Event::Key(key));
+        }
+        *error = None;
+        self.finder = Some(picker);
+    }
+
+    fn is_own_save(&self, request: &Request) -> bool {
+        let Request::Save(edit) = request else {
+            return false;
+        };
+        match &**edit {
+            Edit::Update { original, .. } | Edit::Delete { original, .. } => {
+                original.id == self.transaction.id
+            }
+            _ => false,
+        }
+    }
+
+    /// Keeps the payees and categories for the edit form, `None` if `response` is not for them
+    fn on_choices(&mut self, response: &Response) -> Option<bool> {
+        let (Request::Payees { budget_id } | Request::Categories { budget_id }) = &response.request
+        else {
+            return None;
+        };
+        if *budget_id != self.budget.id.to_string() {
+            return Some(false);
+        }
+        match &response.payload {
+            Ok(Payload::Payees(payees)) => self.payees = Some(payees.clone()),
+            Ok(Payload::Categories(categories)) => self.categories = Some(categories.clone()),
+            Ok(_) => return Some(false),
+            Err(e) => match &mut self.state {
+                State::Edit { error, .. } => *error = Some(e.to_string()),
+                _ => return Some(false),
+            },
+        }
+        Some(true)
+    }
+
+    fn render_edit<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let State::Edit {
+            inputs,
+            field,
+            error,
+            ..
+        } = &self.state
+        else {
+            return;
+        };
+        let area = centered_rect(60, 80, area);
+        let form = block().title("Edit transaction");
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(1),
+            ])
+            .split(form.inner(area));
+        frame.render_widget(Clear, area);
+        frame.render_widget(form, area);
+        for (i, input) in inputs.iter().enumerate() {
+            input.render(frame, rows[i], EDIT_FIELDS[i], i == *field);
+        }
+        let hint = match (error, *field) {
+            (Some(error), _) => error.clone(),
+            (None, PAYEE | CATEGORY) => "tab: next field   enter: pick   esc: cancel".to_string(),
+            (None, _) => "tab: next field   enter: save   esc: cancel".to_string(),
+        };
+        frame.render_widget(Paragraph::new(hint), rows[5]);
+        if let Some(finder) = &self.finder {
+            finder.render(frame, area);
+        }
     }
 }
 
-impl Page for TransactionPage {
-    fn ui(&mut self, frame: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect) {
-        self.transaction.render(frame, area);
+/// The fields of the form that differ from `t`, `None` if nothing changed
+fn changes(
+    t: &TransactionDetail,
+    inputs: &[TextInput],
+    payee_id: Option<Uuid>,
+    category_id: Option<Uuid>,
+) -> Result<Option<SaveTransaction>, String> {
+    let mut save = SaveTransaction::new();
+    let (date, payee, memo, amount) = (inputs[0].trim(), inputs[1].trim(), &*inputs[3], &inputs[4]);
+
+    if date != t.date {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("`{date}` is not a date like 2024-05-31"))?;
+        save.date = Some(date.to_string());
+    }
+    match payee_id {
+        Some(id) if payee_id != t.payee_id => save.payee_id = Some(id),
+        Some(_) => {}
+        // A payee that does not exist yet is created by name
+        None if payee != t.payee_name.as_deref().unwrap_or_default() => {
+            if payee.is_empty() {
+                return Err("The payee can't be empty".to_string());
+            }
+            save.payee_name = Some(payee.to_string());
+        }
+        None => {}
+    }
+    if category_id != t.category_id {
+        save.category_id = category_id;
+    }
+    if memo != t.memo.as_deref().unwrap_or_default() {
+        save.memo = Some(memo.to_string());
+    }
+    let amount = parse_amount(amount, false).ok_or(format!("`{}` is not an amount", &**amount))?;
+    if amount != t.amount {
+        save.amount = Some(amount);
     }
 
-    fn update(&mut self, _api: &mut YnabApi) -> io::Result<Message> {
-        if let Ok(false) = poll(Duration::from_millis(200)) {
-            return noop();
+    Ok((save != SaveTransaction::new()).then_some(save))
+}
+
+/// The account and the id of the other side, if `t` is a transfer
+pub(crate) fn transfer(t: &TransactionDetail) -> Option<(Uuid, String)> {
+    let account_id = t.transfer_account_id?;
+    Some((account_id, t.transfer_transaction_id.clone()?))
+}
+
+impl<B: Backend> Page<B> for TransactionPage {
+    fn ui(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let (panes_area, help_area) = split_vertical(90, area);
+        let fields = transaction_fields(&self.transaction);
+        let name = match self.deleted {
+            true => "Transaction (deleted)",
+            false => "Transaction",
+        };
+        let name = title(name, self.saving);
+        if self.transfer.is_none() {
+            render_fields(frame, panes_area, &name, &fields);
+        } else {
+            let sides = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(panes_area);
+            render_fields(frame, sides[0], &name, &fields);
+            let other_side = match &self.other_side {
+                Some(Ok(t)) => transaction_fields(t),
+                Some(Err(e)) => vec![("Error", e.clone())],
+                None => vec![],
+            };
+            let other_title = title("Other side", self.other_side.is_none());
+            render_fields(frame, sides[1], &other_title, &other_side);
         }
-        let event = read()?;
 
-        // Global Keybinds, override any PageState Keybinds
-        if let Event::Key(key) = event {
-            match key.code {
-                KeyCode::Char('b') => return Ok(Message::Back),
-                _ => (),
+        let help = match &self.note {
+            Some(note) => note.clone(),
+            None => {
+                let mut hints = vec![
+                    (Action::ToggleCleared, "clear"),
+                    (Action::Approve, "approve"),
+                    (Action::Edit, "edit"),
+                    (Action::Delete, "delete"),
+                ];
+                if self.transfer.is_some() {
+                    hints.push((Action::FollowTransfer, "other side"));
+                }
+                hints.retain(|(action, _)| Page::<B>::actions(self).contains(action));
+                hints.push((Action::Back, "back"));
+                keymap::get().hints(&hints)
+            }
+        };
+        frame.render_widget(Paragraph::new(help).block(block()), help_area);
+
+        match &self.state {
+            State::View => {}
+            State::Edit { .. } => self.render_edit(frame, area),
+            State::ConfirmDelete => {
+                let t = &self.transaction;
+                let keys =
+                    keymap::get().hints(&[(Action::Select, "delete"), (Action::Cancel, "keep")]);
+                let message = format!(
+                    "Delete {} {} of {}?\n\n{keys}",
+                    t.date,
+                    t.payee_name.as_deref().unwrap_or_default(),
+                    Column::Amount.cell(t)
+                );
+                render_popup_message("Delete", 50, 30, area, Alignment::Center, &message, frame)
             }
+            State::Error(error, _) => render_error_popup(error, false, area, frame),
         }
+    }
 
+    fn update(&mut self, event: Event, worker: &mut Worker) -> io::Result<Message<B>> {
+        self.note = None;
+        if let State::Edit { .. } = self.state {
+            self.edit(event, worker);
+            return noop();
+        }
+        let Event::Key(key) = event else {
+            return noop();
+        };
+        let action = keymap::get().action(&key, Page::<B>::actions(self));
+        match (&self.state, action) {
+            (State::ConfirmDelete, Some(Action::Select)) => {
+                self.state = State::View;
+                let edit = Edit::Delete {
+                    budget_id: self.budget.id.to_string(),
+                    original: self.transaction.clone(),
+                };
+                self.save(edit, worker);
+            }
+            // Saved again with the reloads that end `saving`
+            (State::Error(_, Request::Save(edit)), Some(Action::Refresh)) => {
+                let edit = (**edit).clone();
+                self.state = State::View;
+                self.save(edit, worker);
+            }
+            (State::ConfirmDelete | State::Error(..), Some(Action::Cancel)) => {
+                self.state = State::View
+            }
+            (State::View, Some(Action::ToggleCleared)) => self.toggle_cleared(worker),
+            (State::View, Some(Action::Approve)) => self.approve(worker),
+            (State::View, Some(Action::Edit)) => self.start_edit(worker),
+            (State::View, Some(Action::Delete)) => self.state = State::ConfirmDelete,
+            (State::View, Some(Action::FollowTransfer)) => {
+                if let Some((account_id, transaction_id)) = &self.transfer {
+                    let page = AccountsPage::at_transaction(
+                        self.budget.clone(),
+                        *account_id,
+                        transaction_id.clone(),
+                        worker,
+                    );
+                    return Ok(Message::NewPage(Box::new(page)));
+                }
+            }
+            _ => {}
+        }
         noop()
     }
 
+    fn on_response(&mut self, response: &Response) -> bool {
+        if self.is_own_save(&response.request) {
+            match &response.payload {
+                Ok(_) => {
+                    if let Request::Save(edit) = &response.request {
+                        self.deleted = matches!(**edit, Edit::Delete { .. });
+                    }
+                }
+                Err(e) => {
+                    self.saving = false;
+                    self.state = State::Error(e.clone(), response.request.clone());
+                }
+            }
+            return true;
+        }
+
+        if let Some(changed) = self.on_choices(response) {
+            return changed;
+        }
+
+        let Request::TransactionsByAccount {
+            budget_id,
+            account_id,
+        } = &response.request
+        else {
+            return false;
+        };
+        if *budget_id != self.budget.id.to_string() {
+            return false;
+        }
+
+        if *account_id == self.transaction.account_id.to_string() {
+            if !self.saving {
+                return false;
+            }
+            self.saving = false;
+            if let Ok(Payload::Transactions(transactions)) = &response.payload {
+                if let Some(t) = transactions.iter().find(|t| t.id == self.transaction.id) {
+                    self.transaction = t.clone();
+                }
+            }
+            return true;
+        }
+
+        let Some((transfer_account, transaction_id)) = &self.transfer else {
+            return false;
+        };
+        if *account_id != transfer_account.to_string() {
+            return false;
+        }
+        self.other_side = Some(match &response.payload {
+            Ok(Payload::Transactions(transactions)) => transactions
+                .iter()
+                .find(|t| t.id == *transaction_id)
+                .cloned()
+                .ok_or_else(|| "Not found".to_string()),
+            Ok(_) => return false,
+            Err(e) => Err(e.to_string()),
+        });
+        true
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        match (&self.state, self.deleted, &self.transfer) {
+            (State::Edit { .. }, ..) => &[],
+            (State::ConfirmDelete, ..) => &[Action::Select, Action::Cancel],
+            (State::Error(..), ..) => &[Action::Refresh, Action::Cancel],
+            (State::View, true, _) => &[],
+            (State::View, false, Some(_)) => &[
+                Action::ToggleCleared,
+                Action::Approve,
+                Action::Edit,
+                Action::Delete,
+                Action::FollowTransfer,
+            ],
+            (State::View, false, None) => &[
+                Action::ToggleCleared,
+                Action::Approve,
+                Action::Edit,
+                Action::Delete,
+            ],
+        }
+    }
+
+    fn captures_input(&self) -> bool {
+        matches!(self.state, State::Edit { .. })
+    }
+
     fn name(&self) -> String {
         String::from("Inspect Transaction")
     }
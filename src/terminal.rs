@@ -0,0 +1,21 @@
+//! Handing the terminal back to the shell, from the normal exit and from the panic hook
+use crossterm::{
+    cursor::Show,
+    event::{DisableBracketedPaste, DisableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+use std::io;
+
+/// Leaves raw mode and the alternate screen and shows the cursor again. Safe to call more than
+/// once, and without a tty: raw mode is only left if it was entered.
+pub fn restore(out: &mut impl io::Write) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        out,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        Show
+    )
+}
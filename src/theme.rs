@@ -0,0 +1,290 @@
+//! The colors and text styles of every widget, from a built-in theme or one from `[themes]` in
+//! the config
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
+use tui::style::{Color, Modifier, Style};
+use ynab_openapi::models::transaction_detail::FlagColor;
+
+use crate::config;
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// The themes `theme` can name without defining them
+pub const BUILT_IN: &[&str] = &["dark", "light"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Rows of lists and tables
+    pub text: Style,
+    pub border: Style,
+    /// The border of the widget that has the focus
+    pub active_border: Style,
+    /// The selected item of a list
+    pub highlight: Style,
+    /// The selected row of a table, and the current tab
+    pub selected_row: Style,
+    /// The characters the fuzzy finder matched
+    pub matched: Style,
+    /// Field names in the detail pane
+    pub label: Style,
+    /// Rows that are left out, like duplicates in an import
+    pub dim: Style,
+    pub inflow: Style,
+    pub outflow: Style,
+    pub uncleared: Style,
+    pub unapproved: Style,
+    pub flag_red: Style,
+    pub flag_orange: Style,
+    pub flag_yellow: Style,
+    pub flag_green: Style,
+    pub flag_blue: Style,
+    pub flag_purple: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// The names of the styles a theme in the config can set
+    pub const STYLES: &'static [&'static str] = &[
+        "text",
+        "border",
+        "active_border",
+        "highlight",
+        "selected_row",
+        "matched",
+        "label",
+        "dim",
+        "inflow",
+        "outflow",
+        "uncleared",
+        "unapproved",
+        "flag_red",
+        "flag_orange",
+        "flag_yellow",
+        "flag_green",
+        "flag_blue",
+        "flag_purple",
+    ];
+
+    /// Light text on the terminal's dark background
+    pub fn dark() -> Self {
+        let fg = |color| Style::default().fg(color);
+        Self {
+            text: Style::default(),
+            border: Style::default(),
+            active_border: fg(Color::Cyan),
+            highlight: fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            selected_row: Style::default().add_modifier(Modifier::REVERSED),
+            matched: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            label: Style::default().add_modifier(Modifier::BOLD),
+            dim: Style::default().add_modifier(Modifier::DIM),
+            inflow: fg(Color::Green),
+            outflow: fg(Color::LightRed),
+            uncleared: fg(Color::Yellow),
+            unapproved: Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC),
+            flag_red: fg(Color::LightRed),
+            flag_orange: fg(Color::Indexed(208)),
+            flag_yellow: fg(Color::Yellow),
+            flag_green: fg(Color::LightGreen),
+            flag_blue: fg(Color::LightBlue),
+            flag_purple: fg(Color::LightMagenta),
+        }
+    }
+
+    /// Dark text on the terminal's light background
+    pub fn light() -> Self {
+        let fg = |color| Style::default().fg(color);
+        Self {
+            active_border: fg(Color::Blue),
+            highlight: fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            matched: fg(Color::Blue).add_modifier(Modifier::BOLD),
+            inflow: fg(Color::Green),
+            outflow: fg(Color::Red),
+            uncleared: fg(Color::Magenta),
+            flag_red: fg(Color::Red),
+            flag_orange: fg(Color::Indexed(166)),
+            flag_yellow: fg(Color::Indexed(136)),
+            flag_green: fg(Color::Green),
+            flag_blue: fg(Color::Blue),
+            flag_purple: fg(Color::Magenta),
+            ..Self::dark()
+        }
+    }
+
+    /// The theme called `name`, dark if `None`. Themes from the config start from the built-in
+    /// one named by their `base`, dark by default, and replace the styles they set.
+    pub fn new(
+        name: Option<&str>,
+        themes: &BTreeMap<String, HashMap<String, String>>,
+    ) -> Result<Self, String> {
+        let name = name.unwrap_or("dark");
+        if let Some(styles) = themes.get(name) {
+            let base = styles.get("base").map_or("dark", String::as_str);
+            let mut theme = Self::built_in(base).ok_or_else(|| {
+                format!("Unknown base `{base}` in [themes.{name}], expected dark or light")
+            })?;
+            for (style, spec) in styles.iter().filter(|(style, _)| *style != "base") {
+                let slot = theme.style_mut(style).ok_or_else(|| {
+                    format!(
+                        "Unknown style `{style}` in [themes.{name}], expected base or one of {}",
+                        Self::STYLES.join(", ")
+                    )
+                })?;
+                *slot = parse_style(spec).map_err(|e| format!("[themes.{name}] {style}: {e}"))?;
+            }
+            return Ok(theme);
+        }
+
+        Self::built_in(name).ok_or_else(|| {
+            let names: Vec<&str> = BUILT_IN
+                .iter()
+                .copied()
+                .chain(themes.keys().map(String::as_str))
+                .collect();
+            format!(
+                "Unknown theme `{name}`, expected one of {}",
+                names.join(", ")
+            )
+        })
+    }
+
+    fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "text" => &mut self.text,
+            "border" => &mut self.border,
+            "active_border" => &mut self.active_border,
+            "highlight" => &mut self.highlight,
+            "selected_row" => &mut self.selected_row,
+            "matched" => &mut self.matched,
+            "label" => &mut self.label,
+            "dim" => &mut self.dim,
+            "inflow" => &mut self.inflow,
+            "outflow" => &mut self.outflow,
+            "uncleared" => &mut self.uncleared,
+            "unapproved" => &mut self.unapproved,
+            "flag_red" => &mut self.flag_red,
+            "flag_orange" => &mut self.flag_orange,
+            "flag_yellow" => &mut self.flag_yellow,
+            "flag_green" => &mut self.flag_green,
+            "flag_blue" => &mut self.flag_blue,
+            "flag_purple" => &mut self.flag_purple,
+            _ => return None,
+        })
+    }
+
+    /// Inflow for positive amounts, outflow for negative ones
+    pub fn amount(&self, milliunits: i64) -> Style {
+        match milliunits {
+            0 => self.text,
+            a if a > 0 => self.inflow,
+            _ => self.outflow,
+        }
+    }
+
+    pub fn flag(&self, flag: FlagColor) -> Style {
+        match flag {
+            FlagColor::Red => self.flag_red,
+            FlagColor::Orange => self.flag_orange,
+            FlagColor::Yellow => self.flag_yellow,
+            FlagColor::Green => self.flag_green,
+            FlagColor::Blue => self.flag_blue,
+            FlagColor::Purple => self.flag_purple,
+        }
+    }
+}
+
+/// Reads a style written like `"bold red on black"`: modifiers, then the text color, then `on`
+/// and the background color, each optional. Colors are names like `lightblue`, `#rrggbb` or
+/// a number from the 256 color palette.
+pub fn parse_style(s: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = s.split_whitespace();
+    while let Some(word) = words.next() {
+        let word = word.to_lowercase();
+        if word == "on" {
+            let color = words.next().ok_or("expected a color after `on`")?;
+            style = style.bg(parse_color(color)?);
+        } else if let Some(modifier) = parse_modifier(&word) {
+            style = style.add_modifier(modifier);
+        } else {
+            style = style.fg(parse_color(&word)?);
+        }
+    }
+    Ok(style)
+}
+
+fn parse_modifier(word: &str) -> Option<Modifier> {
+    Some(match word {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" => Modifier::UNDERLINED,
+        "reversed" => Modifier::REVERSED,
+        "crossed_out" => Modifier::CROSSED_OUT,
+        _ => return None,
+    })
+}
+
+fn parse_color(s: &str) -> Result<Color, String> {
+    let color = match s.to_lowercase().replace(['_', '-'], "").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        // Checked for ASCII so the slices below can't cut through a character
+        hex if hex.starts_with('#') && hex.len() == 7 => {
+            if !hex.is_ascii() {
+                return Err(format!("invalid color `{s}`"));
+            }
+            let channel = |i| u8::from_str_radix(&hex[i..i + 2], 16);
+            match (channel(1), channel(3), channel(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                _ => return Err(format!("invalid color `{s}`")),
+            }
+        }
+        n => match n.parse() {
+            Ok(index) => Color::Indexed(index),
+            Err(_) => return Err(format!("unknown color or modifier `{s}`")),
+        },
+    };
+    Ok(color)
+}
+
+/// The theme from the config, or the dark theme if there is none, e.g. in tests
+pub fn get() -> &'static Theme {
+    THEME.get_or_init(|| {
+        let config = config::get();
+        Theme::new(config.theme.as_deref(), &config.themes).unwrap_or_default()
+    })
+}
@@ -1,9 +1,22 @@
 use tui::layout::*;
+use ynab_openapi::models::BudgetSummary;
 
 pub fn milicent_to_dollars(amount: i64) -> f64 {
     amount as f64 / 1000.0
 }
 
+/// Milliunits as an exact decimal for files and scripts, e.g. `-12.30`. The third decimal is
+/// only kept for currencies that use it.
+pub fn plain_amount(milliunits: i64) -> String {
+    let sign = if milliunits < 0 { "-" } else { "" };
+    let (whole, fraction) = (milliunits.abs() / 1000, milliunits.abs() % 1000);
+    if fraction % 10 == 0 {
+        format!("{sign}{whole}.{:02}", fraction / 10)
+    } else {
+        format!("{sign}{whole}.{fraction:03}")
+    }
+}
+
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -41,32 +54,19 @@ pub fn split_vertical(constraint: u16, area: Rect) -> (Rect, Rect) {
     (splits[0], splits[1])
 }
 
-pub fn master_stack_layout(children: u16, master_width: u16, area: Rect) -> (Rect, Vec<Rect>) {
-    let stack_width = 100 - master_width;
-    let chunks = Layout::default()
-        .constraints([
-            Constraint::Percentage(stack_width),
-            Constraint::Percentage(master_width),
-        ])
-        .direction(Direction::Horizontal)
-        .split(area);
-
-    let stack_area = chunks[0];
-    let master_area = chunks[1];
-
-    let stack_element_size = 100 / children;
-    let stack_constraints: Vec<Constraint> = (0..children)
-        .map(|_| Constraint::Percentage(stack_element_size))
-        .collect();
-
-    let stack = Layout::default()
-        .constraints(stack_constraints)
-        .direction(Direction::Vertical)
-        .split(stack_area);
-
-    (master_area, stack)
-}
-
-pub unsafe fn force_mut_ref<T>(a: &T) -> &mut T {
-    &mut *((a as *const T) as *mut T)
+/// Matches the id, the name ignoring case, or the start of exactly one name
+pub fn find_budget<'a>(budgets: &'a [BudgetSummary], name: &str) -> Option<&'a BudgetSummary> {
+    let name = name.to_lowercase();
+    budgets
+        .iter()
+        .find(|b| b.id.to_string() == name || b.name.to_lowercase() == name)
+        .or_else(|| {
+            let mut matches = budgets
+                .iter()
+                .filter(|b| b.name.to_lowercase().starts_with(&name));
+            match (matches.next(), matches.next()) {
+                (Some(budget), None) => Some(budget),
+                _ => None,
+            }
+        })
 }
@@ -0,0 +1,114 @@
+use clap::Parser;
+use ynab_tui::{
+    cli::{self, CliError, Command},
+    data_layer::FixtureSource,
+};
+
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn run(args: &str) -> Result<String, CliError> {
+    let args = Args::parse_from(std::iter::once("ynab_tui").chain(args.split_whitespace()));
+    let mut source = FixtureSource::load("fixtures").unwrap();
+    let mut out = vec![];
+    cli::run(&args.command, &mut source, &mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn lists_budgets_as_a_table() {
+    let out = run("budgets").unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert!(lines[0].starts_with("Name"));
+    assert!(lines[1].starts_with("Family"));
+    assert!(lines[2].starts_with("Personal"));
+}
+
+#[test]
+fn needs_a_budget_when_there_are_several() {
+    let err = run("accounts").unwrap_err();
+    assert!(err.to_string().contains("more than one budget"));
+
+    let err = run("accounts --budget work").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "No budget `work`, expected one of Family, Personal"
+    );
+}
+
+#[test]
+fn prints_transactions_as_csv() {
+    let out = run("transactions -b personal --account checking --format csv").unwrap();
+    let mut reader = csv::Reader::from_reader(out.as_bytes());
+    let headers = reader.headers().unwrap().clone();
+    assert!(headers.iter().any(|h| h == "Date"));
+    let amount = headers.iter().position(|h| h == "Amount").unwrap();
+
+    let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+    assert!(!rows.is_empty());
+    assert!(rows.iter().all(|r| r[amount].parse::<f64>().is_ok()));
+}
+
+#[test]
+fn filters_transactions_by_date_and_text() {
+    let all = run("transactions -b personal --format json").unwrap();
+    let all: Vec<serde_json::Value> = serde_json::from_str(&all).unwrap();
+    let since = all[all.len() / 2]["date"].as_str().unwrap().to_string();
+
+    let out = run(&format!(
+        "transactions -b personal --since {since} --format json"
+    ))
+    .unwrap();
+    let recent: Vec<serde_json::Value> = serde_json::from_str(&out).unwrap();
+    assert!(recent.len() < all.len());
+    assert!(recent
+        .iter()
+        .all(|t| t["date"].as_str().unwrap() >= since.as_str()));
+
+    let payee = all[0]["payee_name"].as_str().unwrap();
+    let payee = payee.split(' ').next().unwrap().to_uppercase();
+    let out = run(&format!(
+        "transactions -b personal --filter {payee} --format json"
+    ))
+    .unwrap();
+    let matching: Vec<serde_json::Value> = serde_json::from_str(&out).unwrap();
+    assert!(!matching.is_empty());
+}
+
+#[test]
+fn rejects_dates_that_are_not_dates() {
+    let err = Args::try_parse_from(["ynab_tui", "transactions", "--since", "2024-13-01"])
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    assert!(err
+        .to_string()
+        .contains("`2024-13-01` is not a date like 2024-01-31"));
+}
+
+#[test]
+fn totals_the_balances() {
+    let out = run("balance -b personal --format json").unwrap();
+    let balance: serde_json::Value = serde_json::from_str(&out).unwrap();
+    let sum: i64 = balance["accounts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["balance"].as_i64().unwrap())
+        .sum();
+    assert_eq!(balance["total"].as_i64().unwrap(), sum);
+}
+
+#[test]
+fn lists_the_categories_of_a_month() {
+    let out = run("categories -b personal").unwrap();
+    assert!(out.lines().next().unwrap().starts_with("Group"));
+    assert!(out.lines().count() > 1);
+
+    let out = run("categories -b personal --month 2024-04 --format json").unwrap();
+    let categories: Vec<serde_json::Value> = serde_json::from_str(&out).unwrap();
+    assert!(!categories.is_empty());
+}
@@ -0,0 +1,180 @@
+//! The app on the fixture budgets in a `TestBackend`, driven by keys written as in the config
+// Each test crate uses its own part of this
+#![allow(dead_code)]
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use tui::{backend::TestBackend, style::Modifier, Terminal};
+use ynab_openapi::models::{
+    Account, BudgetSummary, CategoryGroupWithCategories, MonthDetail, MonthSummary, Payee,
+    TransactionDetail,
+};
+use ynab_tui::{
+    app::App,
+    config::parse_key,
+    data_layer::{
+        ApiResult, BudgetDataSource, DataError, Edit, FixtureBudget, FixtureSource, Worker,
+    },
+};
+
+/// The fixtures, with the first `failures` saves rejected the way YNAB rejects a bad edit
+pub struct FlakySource {
+    source: FixtureSource,
+    failures: usize,
+}
+
+impl FlakySource {
+    pub fn new(failures: usize) -> Self {
+        Self {
+            source: FixtureSource::load("fixtures").unwrap(),
+            failures,
+        }
+    }
+}
+
+impl BudgetDataSource for FlakySource {
+    fn get_budgets(&mut self) -> ApiResult<Vec<BudgetSummary>> {
+        self.source.get_budgets()
+    }
+
+    fn get_accounts(&mut self, budget_id: &str) -> ApiResult<Vec<Account>> {
+        self.source.get_accounts(budget_id)
+    }
+
+    fn get_transactions(&mut self, budget_id: &str) -> ApiResult<Vec<TransactionDetail>> {
+        self.source.get_transactions(budget_id)
+    }
+
+    fn get_transactions_by_account(
+        &mut self,
+        budget_id: &str,
+        account_id: &str,
+    ) -> ApiResult<Vec<TransactionDetail>> {
+        self.source
+            .get_transactions_by_account(budget_id, account_id)
+    }
+
+    fn get_categories(&mut self, budget_id: &str) -> ApiResult<Vec<CategoryGroupWithCategories>> {
+        self.source.get_categories(budget_id)
+    }
+
+    fn get_payees(&mut self, budget_id: &str) -> ApiResult<Vec<Payee>> {
+        self.source.get_payees(budget_id)
+    }
+
+    fn get_months(&mut self, budget_id: &str) -> ApiResult<Vec<MonthSummary>> {
+        self.source.get_months(budget_id)
+    }
+
+    fn get_month(&mut self, budget_id: &str, month: &str) -> ApiResult<MonthDetail> {
+        self.source.get_month(budget_id, month)
+    }
+
+    fn save(&mut self, edit: &Edit) -> ApiResult<()> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(DataError::Api {
+                status: 400,
+                detail: "rejected".to_string(),
+            });
+        }
+        self.source.save(edit)
+    }
+}
+
+pub struct Screen {
+    pub app: App<TestBackend>,
+    pub terminal: Terminal<TestBackend>,
+}
+
+impl Screen {
+    /// The homepage at 100x30, with the budgets loaded
+    pub fn new() -> Self {
+        Self::with_size(100, 30)
+    }
+
+    pub fn with_size(width: u16, height: u16) -> Self {
+        Self::on(FixtureSource::load("fixtures").unwrap(), width, height)
+    }
+
+    /// The homepage at 100x30 on other budgets, e.g. the fixtures with something taken out
+    pub fn with_budgets(budgets: Vec<FixtureBudget>) -> Self {
+        Self::on(FixtureSource::new(budgets), 100, 30)
+    }
+
+    /// The homepage at 100x30 on any source, e.g. a `FlakySource`
+    pub fn with_source(source: impl BudgetDataSource + 'static) -> Self {
+        Self::on(source, 100, 30)
+    }
+
+    fn on(source: impl BudgetDataSource + 'static, width: u16, height: u16) -> Self {
+        let worker = Worker::spawn(source);
+        let mut screen = Self {
+            app: App::new(worker),
+            terminal: Terminal::new(TestBackend::new(width, height)).unwrap(),
+        };
+        screen.app.settle();
+        screen
+    }
+
+    /// Presses each key, written as in the config and separated by spaces, e.g. `j enter ctrl-t`
+    pub fn press(&mut self, keys: &str) {
+        for key in keys.split_whitespace() {
+            self.send(Event::Key(parse_key(key).unwrap()));
+        }
+    }
+
+    /// Types `text` a character at a time, spaces included
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.send(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )));
+        }
+    }
+
+    /// Runs a `:` command, e.g. `budget pers`
+    pub fn command(&mut self, command: &str) {
+        self.press(":");
+        self.type_text(command);
+        self.press("enter");
+    }
+
+    /// Draws first, the way the terminal shows the app before a key or click, and waits for the
+    /// responses to the event
+    pub fn send(&mut self, event: Event) {
+        self.app.draw(&mut self.terminal).unwrap();
+        assert!(self.app.handle_event(event).unwrap(), "the app quit");
+        self.app.settle();
+    }
+
+    pub fn lines(&mut self) -> Vec<String> {
+        self.app.draw(&mut self.terminal).unwrap();
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        let cells: Vec<&str> = buffer.content.iter().map(|c| c.symbol.as_str()).collect();
+        cells.chunks(width).map(|line| line.concat()).collect()
+    }
+
+    pub fn line(&mut self, row: u16) -> String {
+        self.lines().swap_remove(row as usize)
+    }
+
+    /// Every line, one after the other
+    pub fn text(&mut self) -> String {
+        self.lines().concat()
+    }
+
+    /// The highlighted row of the transaction table, right of the accounts
+    pub fn highlighted(&mut self) -> String {
+        self.app.draw(&mut self.terminal).unwrap();
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        let line = buffer
+            .content
+            .chunks(width)
+            .find(|line| line[30].modifier.contains(Modifier::REVERSED))
+            .expect("no row is highlighted");
+        line[20..].iter().map(|c| c.symbol.as_str()).collect()
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::Duration;
+use std::{collections::HashMap, fs, path::PathBuf};
+use ynab_tui::config::{parse_duration, Config, ConfigError, Secret};
+
+fn token_file(name: &str, mode: u32) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ynab_tui_{name}_{}", std::process::id()));
+    fs::write(&path, "file-token\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+    path
+}
+
+#[test]
+fn reads_the_token_from_a_private_file() {
+    let config = Config {
+        token_file: Some(token_file("private", 0o600)),
+        ..Default::default()
+    };
+    assert_eq!(config.token().unwrap().expose(), "file-token");
+}
+
+#[cfg(unix)]
+#[test]
+fn rejects_token_files_readable_by_others() {
+    let config = Config {
+        token_file: Some(token_file("public", 0o644)),
+        ..Default::default()
+    };
+    assert!(matches!(config.token(), Err(ConfigError::TokenFile(..))));
+}
+
+#[test]
+fn reads_the_token_from_a_command() {
+    let config = Config {
+        token_command: Some("echo command-token".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(config.token().unwrap().expose(), "command-token");
+}
+
+#[test]
+fn refreshing_the_token_gives_the_command_no_input() {
+    let config = Config {
+        token_command: Some("read token && echo \"$token\"".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        config.refresh_token(),
+        Err(ConfigError::TokenCommand(_))
+    ));
+}
+
+#[test]
+fn reports_failing_token_commands() {
+    let config = Config {
+        token_command: Some("exit 3".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(config.token(), Err(ConfigError::TokenCommand(_))));
+}
+
+#[test]
+fn redacts_secrets_in_debug_output() {
+    let config = Config {
+        token: Some(Secret::new("plain-token".to_string())),
+        ..Default::default()
+    };
+    assert!(!format!("{config:?}").contains("plain-token"));
+}
+
+fn config_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ynab_tui_{name}_{}.toml", std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+const PROFILES: &str = r#"
+token_command = "echo top-level"
+default_profile = "personal"
+
+[profiles.personal]
+default_budget = "Personal"
+
+[profiles.family]
+token_command = "echo family"
+cache_file = "/tmp/family.json"
+default_budget = "Family"
+"#;
+
+#[test]
+fn profiles_override_the_top_level() {
+    let path = config_file("profiles", PROFILES);
+
+    let config = Config::load(Some(&path), Some("family")).unwrap();
+    assert_eq!(config.profile.as_deref(), Some("family"));
+    assert_eq!(config.token().unwrap().expose(), "family");
+    assert_eq!(config.default_budget.as_deref(), Some("Family"));
+    assert_eq!(
+        config.cache_file().unwrap(),
+        PathBuf::from("/tmp/family.json")
+    );
+
+    let config = Config::load(Some(&path), None).unwrap();
+    assert_eq!(config.profile.as_deref(), Some("personal"));
+    assert_eq!(config.token().unwrap().expose(), "top-level");
+}
+
+#[test]
+fn reports_unknown_profiles() {
+    let path = config_file("unknown_profile", PROFILES);
+    let e = Config::load(Some(&path), Some("work")).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Unknown profile `work`, expected one of family, personal"
+    );
+}
+
+#[test]
+fn parses_durations() {
+    assert_eq!(parse_duration("30s"), Ok(Duration::seconds(30)));
+    assert_eq!(parse_duration("5m"), Ok(Duration::minutes(5)));
+    assert_eq!(parse_duration("1h"), Ok(Duration::hours(1)));
+    assert_eq!(
+        parse_duration("abc").unwrap_err(),
+        "invalid duration `abc`, expected a number followed by s, m, h or d"
+    );
+    assert!(parse_duration("").is_err());
+}
+
+#[test]
+fn the_environment_overrides_the_file() {
+    let path = config_file("env", "base_url = \"http://from-the-file\"\n");
+    std::env::set_var("YNAB_BASE_URL", "http://from-the-env");
+    let config = Config::load(Some(&path), None);
+    std::env::remove_var("YNAB_BASE_URL");
+    assert_eq!(
+        config.unwrap().base_url.as_deref(),
+        Some("http://from-the-env")
+    );
+}
+
+fn dotenv(vars: &[(&str, &str)]) -> HashMap<String, String> {
+    vars.iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn dotenv_only_fills_what_the_file_leaves_unset() {
+    let path = config_file("dotenv", "default_budget = \"Family\"\n");
+    let dotenv = dotenv(&[
+        ("YNAB_DEFAULT_BUDGET", "Personal"),
+        ("YNAB_CACHE_FILE", "/tmp/from-dotenv.json"),
+    ]);
+    let config = Config::load_with_dotenv(Some(&path), None, &dotenv).unwrap();
+    assert_eq!(config.default_budget.as_deref(), Some("Family"));
+    assert_eq!(
+        config.cache_file().unwrap(),
+        PathBuf::from("/tmp/from-dotenv.json")
+    );
+}
+
+#[test]
+fn a_configured_token_source_wins_over_a_dotenv_token() {
+    let dotenv = dotenv(&[("YNAB_TOKEN", "from-dotenv")]);
+
+    let path = config_file("dotenv_token", "token_command = \"echo from-command\"\n");
+    let config = Config::load_with_dotenv(Some(&path), None, &dotenv).unwrap();
+    assert_eq!(config.token().unwrap().expose(), "from-command");
+
+    let path = config_file("dotenv_token_only", "");
+    let config = Config::load_with_dotenv(Some(&path), None, &dotenv).unwrap();
+    assert_eq!(config.token().unwrap().expose(), "from-dotenv");
+}
@@ -0,0 +1,50 @@
+use std::{io, sync::mpsc, time::Duration};
+use tui::{backend::TestBackend, Terminal};
+use ynab_tui::{
+    app::App,
+    data_layer::{FixtureSource, Request, Worker},
+    event::{AppEvent, EventSource, ScriptedEvents},
+};
+
+#[test]
+fn the_worker_wakes_the_event_loop() {
+    let (sender, receiver) = mpsc::channel();
+    let mut worker = Worker::spawn(FixtureSource::load("fixtures").unwrap());
+    worker.notify(sender);
+    worker.request(Request::Budgets);
+
+    let event = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(matches!(event, AppEvent::Response));
+    assert!(worker.try_recv().is_some());
+}
+
+/// Records how long the app was willing to wait for each event
+struct Timeouts {
+    events: ScriptedEvents,
+    timeouts: Vec<Option<Duration>>,
+}
+
+impl EventSource for Timeouts {
+    fn next(&mut self, timeout: Option<Duration>) -> io::Result<Option<AppEvent>> {
+        self.timeouts.push(timeout);
+        self.events.next(timeout)
+    }
+
+    fn is_done(&self) -> bool {
+        self.events.is_done()
+    }
+}
+
+#[test]
+fn an_idle_app_waits_for_the_next_event_without_a_timeout() {
+    let mut app = App::new(Worker::spawn(FixtureSource::load("fixtures").unwrap()));
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    app.settle();
+
+    let mut events = Timeouts {
+        events: ScriptedEvents::keys("jk"),
+        timeouts: vec![],
+    };
+    app.run(&mut terminal, &mut events).unwrap();
+    assert_eq!(events.timeouts, [None, None]);
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::Screen;
+use std::fs;
+use ynab_openapi::models::{Account, TransactionDetail};
+use ynab_tui::{
+    data_layer::{BudgetDataSource, FixtureSource},
+    export::{Format, Transactions},
+};
+
+const PERSONAL: &str = "0bc3812b-ee6f-5610-a79d-5c8396015e74";
+
+fn personal() -> (Vec<Account>, Vec<TransactionDetail>) {
+    let mut source = FixtureSource::load("fixtures").unwrap();
+    let accounts = source.get_accounts(PERSONAL).unwrap();
+    let transactions = source.get_transactions(PERSONAL).unwrap();
+    (accounts, transactions)
+}
+
+fn export(format: Format) -> String {
+    let (accounts, transactions) = personal();
+    let mut out = vec![];
+    Transactions::new(&accounts, &transactions)
+        .write(format, &mut out)
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// The amounts of each journal entry, which are separated by blank lines
+fn entries(journal: &str) -> Vec<Vec<(String, f64)>> {
+    journal
+        .split("\n\n")
+        .filter(|entry| !entry.contains(" open "))
+        .map(|entry| {
+            entry
+                .lines()
+                .skip(1)
+                .filter(|line| !line.trim_start().starts_with(';') && !line.contains("memo:"))
+                .map(|line| {
+                    let (account, amount) = line.trim().split_once("  ").unwrap();
+                    let amount = amount.trim().split(' ').next().unwrap();
+                    (account.to_string(), amount.parse().unwrap())
+                })
+                .collect()
+        })
+        .filter(|postings: &Vec<_>| !postings.is_empty())
+        .collect()
+}
+
+#[test]
+fn ledger_entries_balance() {
+    let journal = export(Format::Ledger);
+    let entries = entries(&journal);
+    assert!(!entries.is_empty());
+    for postings in entries {
+        let sum: f64 = postings.iter().map(|(_, amount)| amount).sum();
+        assert!(sum.abs() < 0.001, "{postings:?} does not balance");
+    }
+}
+
+#[test]
+fn splits_become_postings() {
+    let journal = export(Format::Ledger);
+    let split = journal
+        .split("\n\n")
+        .find(|entry| entry.contains("Big Box Store"))
+        .unwrap();
+    assert!(split.lines().filter(|l| l.contains("Expenses:")).count() > 1);
+    assert!(!split.contains("Split"));
+}
+
+#[test]
+fn transfers_are_exported_once() {
+    let journal = export(Format::Ledger);
+    let (_, transactions) = personal();
+    let transfers = transactions
+        .iter()
+        .filter(|t| t.transfer_transaction_id.is_some())
+        .count();
+    let entries = journal
+        .split("\n\n")
+        .filter(|entry| entry.contains("Transfer :"))
+        .count();
+    assert_eq!(entries * 2, transfers);
+}
+
+#[test]
+fn beancount_opens_valid_accounts() {
+    let journal = export(Format::Beancount);
+    assert!(journal.contains("open Liabilities:Credit-Card"));
+    for entry in entries(&journal) {
+        for (account, _) in entry {
+            assert!(journal.contains(&format!("open {account}\n")), "{account}");
+            assert!(!account.contains(' '));
+        }
+    }
+}
+
+#[test]
+fn jsonl_has_a_line_per_transaction() {
+    let (_, transactions) = personal();
+    let out = export(Format::Jsonl);
+    assert_eq!(out.lines().count(), transactions.len());
+    for line in out.lines() {
+        serde_json::from_str::<TransactionDetail>(line).unwrap();
+    }
+}
+
+#[test]
+fn csv_uses_the_table_columns() {
+    let out = export(Format::Csv);
+    assert_eq!(
+        out.lines().next().unwrap(),
+        "Payee,Category,Memo,Amount,Date"
+    );
+}
+
+#[test]
+fn formats_from_file_names() {
+    use std::path::Path;
+    assert_eq!(
+        Format::from_path(Path::new("a.journal")),
+        Some(Format::Ledger)
+    );
+    assert_eq!(
+        Format::from_path(Path::new("a.beancount")),
+        Some(Format::Beancount)
+    );
+    assert_eq!(Format::from_path(Path::new("a.txt")), None);
+}
+
+#[test]
+fn export_command_writes_the_filtered_transactions() {
+    let mut screen = Screen::new();
+    let path = std::env::temp_dir().join(format!("ynab_tui_export_{}.csv", std::process::id()));
+    screen.press("j enter l /");
+    screen.type_text("corner");
+    screen.press("enter");
+    screen.command(&format!("export {}", path.display()));
+
+    let csv = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(csv.lines().count() > 1);
+    assert!(csv.lines().skip(1).all(|l| l.contains("Corner")));
+}
+
+#[test]
+fn export_command_takes_paths_with_spaces() {
+    let mut screen = Screen::new();
+    let dir = std::env::temp_dir().join(format!("ynab_tui export {}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    screen.press("j enter l");
+    let (csv, jsonl) = (dir.join("out file"), dir.join("my out.jsonl"));
+    screen.command(&format!("export csv {}", csv.display()));
+    screen.command(&format!("export {}", jsonl.display()));
+
+    // The second has no format and goes by its extension
+    assert!(fs::read_to_string(csv).unwrap().lines().count() > 1);
+    assert!(fs::read_to_string(jsonl).unwrap().starts_with('{'));
+    fs::remove_dir_all(&dir).unwrap();
+}
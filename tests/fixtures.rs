@@ -0,0 +1,40 @@
+use ynab_openapi::models::transaction_detail::{Cleared, FlagColor};
+use ynab_tui::data_layer::{convert::*, BudgetDataSource, FixtureSource};
+
+#[test]
+fn loads_every_budget_in_the_directory() {
+    let mut source = FixtureSource::load("fixtures").unwrap();
+    let budgets = source.get_budgets().unwrap();
+    let names: Vec<&str> = budgets.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, ["Family", "Personal"]);
+
+    let counts: Vec<(usize, usize)> = budgets
+        .iter()
+        .map(|b| {
+            let id = b.id.to_string();
+            let accounts = source.get_accounts(&id).unwrap().len();
+            let transactions = source.get_transactions(&id).unwrap().len();
+            (accounts, transactions)
+        })
+        .collect();
+    assert_eq!(counts, [(2, 7), (4, 33)]);
+}
+
+#[test]
+fn converts_there_and_back() {
+    let cleared = [Cleared::Cleared, Cleared::Uncleared, Cleared::Reconciled];
+    for c in cleared {
+        assert_eq!(cleared_to_detail(cleared_to_save(c)), c);
+    }
+    let flags = [
+        FlagColor::Red,
+        FlagColor::Orange,
+        FlagColor::Yellow,
+        FlagColor::Green,
+        FlagColor::Blue,
+        FlagColor::Purple,
+    ];
+    for f in flags {
+        assert_eq!(flag_to_detail(flag_to_save(f)), f);
+    }
+}
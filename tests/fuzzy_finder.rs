@@ -0,0 +1,102 @@
+mod common;
+
+use common::Screen;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ynab_tui::components::{fuzzy_match, FuzzyFinder, Pick};
+
+fn key(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+fn finder(labels: &[&str]) -> FuzzyFinder<String> {
+    let items = labels.iter().map(|l| (l.to_string(), l.to_string()));
+    FuzzyFinder::new("Test", items.collect())
+}
+
+fn type_query(finder: &mut FuzzyFinder<String>, query: &str) {
+    for c in query.chars() {
+        finder.handle_event(&key(KeyCode::Char(c)));
+    }
+}
+
+fn labels(finder: &FuzzyFinder<String>, all: &[&str]) -> Vec<String> {
+    finder
+        .matches()
+        .iter()
+        .map(|m| all[m.index].to_string())
+        .collect()
+}
+
+#[test]
+fn matches_characters_in_order_ignoring_case() {
+    let (_, positions) = fuzzy_match("cc", "Corner Cafe").unwrap();
+    assert_eq!(positions, vec![0, 7]);
+    assert!(fuzzy_match("cafe", "Corner Cafe").is_some());
+    assert!(fuzzy_match("efac", "Corner Cafe").is_none());
+}
+
+#[test]
+fn prefers_word_starts() {
+    // Spread over `Corner` the `c`, `a`... would not match at all, at `Cafe` they are a run
+    let (_, positions) = fuzzy_match("cafe", "Corner Cafe").unwrap();
+    assert_eq!(positions, vec![7, 8, 9, 10]);
+}
+
+#[test]
+fn ranks_the_best_matches_first() {
+    let all = ["Credit Card", "Checking", "Cash", "Savings"];
+    let mut finder = finder(&all);
+    assert_eq!(finder.matches().len(), 4);
+
+    type_query(&mut finder, "cc");
+    assert_eq!(labels(&finder, &all), vec!["Credit Card", "Checking"]);
+
+    finder.handle_event(&key(KeyCode::Backspace));
+    finder.handle_event(&key(KeyCode::Backspace));
+    type_query(&mut finder, "s");
+    assert_eq!(labels(&finder, &all), vec!["Savings", "Cash"]);
+}
+
+#[test]
+fn picks_the_selected_match() {
+    let mut finder = finder(&["Family", "Personal"]);
+    type_query(&mut finder, "xyz");
+    assert!(matches!(
+        finder.handle_event(&key(KeyCode::Enter)),
+        Pick::Pending
+    ));
+
+    for _ in 0..3 {
+        finder.handle_event(&key(KeyCode::Backspace));
+    }
+    finder.handle_event(&key(KeyCode::Down));
+    match finder.handle_event(&key(KeyCode::Enter)) {
+        Pick::Picked(label) => assert_eq!(label, "Personal"),
+        _ => panic!("nothing picked"),
+    }
+    assert!(matches!(
+        finder.handle_event(&key(KeyCode::Esc)),
+        Pick::Cancelled
+    ));
+}
+
+#[test]
+fn account_finder_opens_the_picked_account() {
+    let mut screen = Screen::new();
+    screen.press("j enter f");
+    screen.type_text("ca");
+    assert!(screen.text().contains(">> Cash"));
+
+    screen.press("enter");
+    assert_eq!(screen.app.path(), ["Homepage", "Cash"]);
+}
+
+#[test]
+fn budget_finder_opens_the_picked_budget() {
+    let mut screen = Screen::new();
+    screen.press("B");
+    screen.type_text("per");
+    screen.press("enter");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+    assert!(screen.text().contains("Credit Card"));
+}
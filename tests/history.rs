@@ -0,0 +1,81 @@
+mod common;
+
+use common::Screen;
+
+#[test]
+fn forward_reopens_the_page_as_it_was_left() {
+    let mut screen = Screen::new();
+    screen.press("j enter j j");
+    assert_eq!(screen.app.path(), ["Homepage", "Savings"]);
+
+    screen.press("b");
+    assert_eq!(screen.app.path(), ["Homepage"]);
+    screen.press("alt-right");
+    assert_eq!(screen.app.path(), ["Homepage", "Savings"]);
+
+    // Nothing left to go forward to
+    screen.press("ctrl-f");
+    assert_eq!(screen.app.path(), ["Homepage", "Savings"]);
+}
+
+#[test]
+fn opening_a_page_drops_the_forward_history() {
+    let mut screen = Screen::new();
+    screen.press("j enter l j enter");
+    assert_eq!(
+        screen.app.path(),
+        ["Homepage", "All Accounts", "Inspect Transaction"]
+    );
+
+    screen.press("b b j enter ctrl-f");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+}
+
+#[test]
+fn jumps_between_budgets() {
+    let mut screen = Screen::new();
+    screen.press("j enter j");
+    assert_eq!(screen.app.path(), ["Homepage", "Checking"]);
+
+    screen.command("budget fam");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+
+    screen.press("ctrl-o");
+    assert_eq!(screen.app.path(), ["Homepage", "Checking"]);
+    screen.press("ctrl-o tab");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+}
+
+#[test]
+fn breadcrumbs_go_back_to_an_ancestor() {
+    let mut screen = Screen::new();
+    screen.press("j enter l j enter");
+
+    screen.press("- enter");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+    screen.press("alt-right");
+    assert_eq!(
+        screen.app.path(),
+        ["Homepage", "All Accounts", "Inspect Transaction"]
+    );
+
+    screen.press("- 1");
+    assert_eq!(screen.app.path(), ["Homepage"]);
+
+    // Esc leaves the breadcrumbs as they were
+    screen.press("alt-right - h esc");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+}
+
+#[test]
+fn going_back_keeps_the_selection_through_a_reload() {
+    let mut screen = Screen::new();
+    screen.press("j enter l j j");
+    let selected = screen.highlighted();
+
+    // The new trail loads the same budget, which the page left behind receives too
+    screen.command("budget pers");
+    screen.press("ctrl-o");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+    assert_eq!(screen.highlighted(), selected);
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use chrono::NaiveDate;
+use clap::Parser;
+use common::{FlakySource, Screen};
+use std::path::Path;
+use ynab_tui::{
+    cli::{self, Command},
+    config::Bank,
+    data_layer::{BudgetDataSource, FixtureSource},
+    import::{self, ColumnRef, Duplicate, StatementFormat, StatementRow},
+};
+
+const PERSONAL: &str = "0bc3812b-ee6f-5610-a79d-5c8396015e74";
+const CHECKING: &str = "ad356ea4-6eef-54ee-b388-78f1bbbcd23d";
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn statement(format: &str) -> Vec<StatementRow> {
+    let path = format!("fixtures/statements/checking.{format}");
+    import::read(Path::new(&path), &Bank::default()).unwrap()
+}
+
+#[test]
+fn reads_csv_with_guessed_columns() {
+    let rows = statement("csv");
+    assert_eq!(rows.len(), 5);
+    assert_eq!(
+        rows[0],
+        StatementRow {
+            date: date("2024-05-01"),
+            amount: 3_200_000,
+            payee: "Acme Corp".to_string(),
+            memo: Some("May paycheck".to_string()),
+        }
+    );
+    assert_eq!(rows[2].amount, -18_400);
+    assert_eq!(rows[3].memo, None);
+}
+
+#[test]
+fn reads_csv_with_a_bank_profile() {
+    let text = "Account 1234\n\
+                15.05.2024;Bäckerei;12,50\n\
+                16.05.2024;Gehalt;-1.234,56\n";
+    let bank = Bank {
+        delimiter: ';',
+        skip_rows: 1,
+        headers: false,
+        date: Some(ColumnRef::Index(0)),
+        date_format: Some("%d.%m.%Y".to_string()),
+        payee: Some(ColumnRef::Index(1)),
+        amount: Some(ColumnRef::Index(2)),
+        negate: true,
+        decimal_comma: true,
+        ..Default::default()
+    };
+    let rows = import::parse(text, StatementFormat::Csv, &bank).unwrap();
+    assert_eq!(rows[0].date, date("2024-05-15"));
+    assert_eq!(rows[0].payee, "Bäckerei");
+    assert_eq!(rows[0].amount, -12_500);
+    assert_eq!(rows[1].amount, 1_234_560);
+}
+
+#[test]
+fn reports_the_line_of_bad_rows() {
+    let text = "Date,Payee,Amount\n2024-05-01,Shop,1.00\nyesterday,Shop,1.00\n";
+    let err = import::parse(text, StatementFormat::Csv, &Bank::default()).unwrap_err();
+    assert_eq!(err.to_string(), "Line 3: Invalid date `yesterday`");
+
+    let text = "<OFX>\n<STMTTRN>\n<DTPOSTED>2024xx01\n<TRNAMT>1.00\n</STMTTRN>\n</OFX>\n";
+    let err = import::parse(text, StatementFormat::Ofx, &Bank::default()).unwrap_err();
+    assert_eq!(err.to_string(), "Line 5: Invalid DTPOSTED `2024xx01`");
+}
+
+#[test]
+fn ofx_and_qif_agree_with_csv() {
+    let csv = statement("csv");
+    let ofx = statement("ofx");
+    let qif = statement("qif");
+    assert_eq!(ofx.len(), 3);
+    assert_eq!(qif.len(), 3);
+    for rows in [&ofx, &qif] {
+        for (row, expected) in rows.iter().zip(&csv) {
+            assert_eq!(row.date, expected.date);
+            assert_eq!(row.amount, expected.amount);
+        }
+    }
+    assert_eq!(ofx[2].payee, "Coffee Roasters & Co");
+    assert_eq!(qif[2].memo.as_deref(), Some("Beans"));
+}
+
+#[test]
+fn flags_rows_already_in_the_account() {
+    let mut source = FixtureSource::load("fixtures").unwrap();
+    let existing = source
+        .get_transactions_by_account(PERSONAL, CHECKING)
+        .unwrap();
+    let rows = import::preview(statement("csv"), &existing);
+
+    assert_eq!(rows[0].duplicate, Some(Duplicate::Imported));
+    assert!(matches!(
+        &rows[1].duplicate,
+        Some(Duplicate::Similar { date, .. }) if date == "2024-05-02"
+    ));
+    assert!(rows[2..]
+        .iter()
+        .all(|r| r.duplicate.is_none() && r.selected));
+    assert_eq!(rows[3].import_id, "YNAB:-45100:2024-05-28:1");
+    assert_eq!(rows[4].import_id, "YNAB:-45100:2024-05-28:2");
+}
+
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn run(source: &mut FixtureSource, args: &str) -> String {
+    let args = Args::parse_from(std::iter::once("ynab_tui").chain(args.split_whitespace()));
+    let mut out = vec![];
+    cli::run(&args.command, source, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn imports_only_new_rows_once() {
+    let mut source = FixtureSource::load("fixtures").unwrap();
+    let before = source.get_transactions(PERSONAL).unwrap().len();
+    let import = "import fixtures/statements/checking.csv -b personal -a checking";
+
+    let preview = run(&mut source, &format!("{import} --dry-run"));
+    assert_eq!(preview.lines().filter(|l| l.ends_with("new")).count(), 3);
+    assert_eq!(source.get_transactions(PERSONAL).unwrap().len(), before);
+
+    let created = run(&mut source, import);
+    assert_eq!(
+        created.lines().filter(|l| l.ends_with("created")).count(),
+        3
+    );
+    assert_eq!(source.get_transactions(PERSONAL).unwrap().len(), before + 3);
+
+    let again = run(&mut source, import);
+    assert!(!again.contains("created"));
+    assert_eq!(source.get_transactions(PERSONAL).unwrap().len(), before + 3);
+}
+
+/// The import page for the Checking statement, with the rows that are new selected
+fn import_page(mut screen: Screen) -> Screen {
+    screen.command("budget pers");
+    screen.press("j");
+    screen.command("import fixtures/statements/checking.csv");
+    screen
+}
+
+/// The title of the import table
+fn title(screen: &mut Screen) -> String {
+    let lines = screen.lines();
+    lines
+        .into_iter()
+        .find(|l| l.starts_with("┌Import"))
+        .unwrap()
+}
+
+#[test]
+fn retrying_a_failed_import_creates_the_rows() {
+    let mut screen = import_page(Screen::with_source(FlakySource::new(1)));
+    screen.press("c");
+    assert!(screen.text().contains("YNAB error 400: rejected"));
+
+    screen.press("r");
+    assert!(title(&mut screen).contains("0 of 5 selected, created 3─"));
+}
+
+#[test]
+fn toggled_rows_stay_toggled_when_the_account_reloads() {
+    let mut screen = import_page(Screen::new());
+    screen.press("j j j space");
+    assert!(title(&mut screen).contains("2 of 5 selected"));
+
+    // The register in another tab loads the same account
+    screen.command("tabnew pers");
+    screen.press("j [");
+    assert_eq!(screen.app.path(), ["Homepage", "Checking", "Import"]);
+    assert!(title(&mut screen).contains("2 of 5 selected"));
+}
+
+#[test]
+fn import_command_selects_only_the_new_rows() {
+    let mut screen = import_page(Screen::new());
+    assert_eq!(screen.app.path(), ["Homepage", "Checking", "Import"]);
+    assert!(title(&mut screen).contains("into Checking: 3 of 5 selected─"));
+
+    let text = screen.text();
+    assert!(text.contains("[ ] 2024-05-01 Acme Corp"));
+    assert!(text.contains("like 2024-05-02 Landl"));
+    assert_eq!(text.matches("[x]").count(), 3);
+}
@@ -0,0 +1,69 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{collections::HashMap, fs};
+use ynab_tui::{
+    config::{parse_key, Config, ConfigError, Keys},
+    keymap::{Action, Keymap},
+};
+
+fn key(s: &str) -> KeyEvent {
+    parse_key(s).unwrap()
+}
+
+fn keymap(bindings: &[(&str, &[&str])]) -> Result<Keymap, String> {
+    let bindings: HashMap<String, Keys> = bindings
+        .iter()
+        .map(|(action, keys)| {
+            (
+                action.to_string(),
+                Keys(keys.iter().map(|k| key(k)).collect()),
+            )
+        })
+        .collect();
+    Keymap::new(&bindings)
+}
+
+#[test]
+fn config_bindings_replace_the_defaults() {
+    let keymap = keymap(&[("move_down", &["n", "down"])]).unwrap();
+    let actions = &[Action::MoveUp, Action::MoveDown];
+
+    assert_eq!(keymap.action(&key("n"), actions), Some(Action::MoveDown));
+    assert_eq!(keymap.action(&key("j"), actions), None);
+    assert_eq!(keymap.action(&key("k"), actions), Some(Action::MoveUp));
+}
+
+#[test]
+fn keys_only_trigger_the_actions_given() {
+    let keymap = Keymap::default();
+    assert_eq!(
+        keymap.action(&key("r"), &[Action::Refresh]),
+        Some(Action::Refresh)
+    );
+    assert_eq!(keymap.action(&key("r"), &[Action::Back]), None);
+    // Terminals may report shift along with the character it produced
+    let question = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+    assert_eq!(keymap.action(&question, Action::GLOBAL), Some(Action::Help));
+}
+
+#[test]
+fn rejects_unknown_actions() {
+    let error = keymap(&[("jump", &["g"])]).unwrap_err();
+    assert!(error.contains("`jump`"), "{error}");
+
+    let path = std::env::temp_dir().join(format!("ynab_tui_keymap_{}.toml", std::process::id()));
+    fs::write(&path, "[keybindings]\njump = \"g\"\n").unwrap();
+    assert!(matches!(
+        Config::load(Some(&path), None),
+        Err(ConfigError::Keybindings(_))
+    ));
+}
+
+#[test]
+fn help_lists_the_bound_keys() {
+    let keymap = keymap(&[("help", &["f1"])]).unwrap();
+    let help = keymap.help(&[Action::Help, Action::MoveDown]);
+    let lines: Vec<&str> = help.lines().collect();
+
+    assert_eq!(lines[0], "f1        Show this help");
+    assert_eq!(lines[1], "j, down   Move down");
+}
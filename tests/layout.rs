@@ -0,0 +1,59 @@
+use tui::layout::Rect;
+use ynab_tui::{
+    keymap::Action,
+    layout::{PaneLayout, NARROW_WIDTH, SHORT_HEIGHT},
+};
+
+#[test]
+fn side_panel_is_beside_the_main_pane_and_above_it_when_narrow() {
+    let layout = PaneLayout::default();
+
+    let panes = layout.split(Rect::new(0, 0, 100, 30));
+    let side = panes.side.unwrap();
+    assert_eq!((side.width, side.height), (20, 30));
+    assert_eq!((panes.main.x, panes.main.width), (20, 80));
+
+    let panes = layout.split(Rect::new(0, 0, NARROW_WIDTH - 1, 30));
+    let side = panes.side.unwrap();
+    assert_eq!((side.width, side.height), (NARROW_WIDTH - 1, 6));
+    assert_eq!((panes.main.y, panes.main.height), (6, 24));
+}
+
+#[test]
+fn detail_pane_is_under_the_main_pane_unless_too_short() {
+    let mut layout = PaneLayout::default();
+    assert!(layout.apply(Action::ToggleDetail));
+
+    let panes = layout.split(Rect::new(0, 0, 100, 30));
+    let detail = panes.detail.unwrap();
+    assert_eq!((detail.x, detail.y, detail.height), (20, 21, 9));
+    assert_eq!(panes.main.height, 21);
+
+    let panes = layout.split(Rect::new(0, 0, 100, SHORT_HEIGHT - 1));
+    assert_eq!(panes.detail, None);
+}
+
+#[test]
+fn resizing_is_clamped_and_opens_the_pane() {
+    let mut layout = PaneLayout::default();
+    assert!(layout.apply(Action::ToggleSide));
+    assert_eq!(layout.split(Rect::new(0, 0, 100, 30)).side, None);
+
+    for _ in 0..20 {
+        layout.apply(Action::WidenSide);
+    }
+    assert!(layout.side_open);
+    assert_eq!(layout.side, 60);
+
+    for _ in 0..20 {
+        layout.apply(Action::ShrinkDetail);
+    }
+    assert!(layout.detail_open);
+    assert_eq!(layout.detail, 15);
+
+    layout.apply(Action::ResetLayout);
+    assert_eq!((layout.side, layout.detail), (20, 30));
+    assert!(layout.side_open && layout.detail_open);
+
+    assert!(!layout.apply(Action::MoveDown));
+}
@@ -0,0 +1,288 @@
+use chrono::Duration;
+use std::path::Path;
+use ynab_openapi::{
+    apis::{
+        configuration::{ApiKey, Configuration},
+        transactions_api,
+    },
+    models::{PutTransactionWrapper, SaveTransaction},
+};
+use ynab_tui::{
+    data_layer::{BudgetDataSource, DataError, FixtureSource, YnabApi},
+    import,
+    mock_server::MockServer,
+};
+
+const BUDGET: &str = "0bc3812b-ee6f-5610-a79d-5c8396015e74";
+const CHECKING: &str = "ad356ea4-6eef-54ee-b388-78f1bbbcd23d";
+const TOKEN: &str = "test-token";
+
+fn server() -> MockServer {
+    let budgets = FixtureSource::load_budgets("fixtures").unwrap();
+    let server = MockServer::start(budgets).unwrap();
+    server.set_token(TOKEN);
+    server
+}
+
+/// A client with its own cache file, removed first so runs don't affect each other
+fn api(server: &MockServer, name: &str, refresh_duration: Duration) -> YnabApi {
+    let cache_file =
+        std::env::temp_dir().join(format!("ynab_tui_{name}_{}.json", std::process::id()));
+    let cache_file = cache_file.to_str().unwrap();
+    let _ = std::fs::remove_file(cache_file);
+    let _ = std::fs::remove_file(format!("{cache_file}.outbox"));
+
+    let mut api = YnabApi::new(TOKEN, cache_file, refresh_duration);
+    api.set_base_path(&server.base_path());
+    api
+}
+
+fn config(server: &MockServer) -> Configuration {
+    let mut config = Configuration::new();
+    config.base_path = server.base_path();
+    config.api_key = Some(ApiKey {
+        prefix: Some("Bearer".to_string()),
+        key: TOKEN.to_string(),
+    });
+    config
+}
+
+#[test]
+fn fetches_budgets_and_accounts() {
+    let server = server();
+    let mut api = api(&server, "fetch", Duration::hours(1));
+
+    let budgets = api.get_budgets().unwrap();
+    assert_eq!(budgets.len(), 2);
+    assert!(budgets.iter().any(|b| b.name == "Personal"));
+
+    let accounts = api.get_accounts(BUDGET).unwrap();
+    assert!(accounts.iter().any(|a| a.name == "Checking"));
+
+    let transactions = api.get_transactions_by_account(BUDGET, CHECKING).unwrap();
+    assert!(!transactions.is_empty());
+    assert!(transactions
+        .iter()
+        .all(|t| t.account_id.to_string() == CHECKING));
+    assert!(transactions.windows(2).all(|w| w[0].date >= w[1].date));
+}
+
+#[test]
+fn serves_fresh_entries_from_the_cache() {
+    let server = server();
+    let mut api = api(&server, "cache", Duration::hours(1));
+
+    api.get_accounts(BUDGET).unwrap();
+    let requests = server.request_count();
+    api.get_accounts(BUDGET).unwrap();
+    assert_eq!(server.request_count(), requests);
+}
+
+#[test]
+fn refetches_expired_entries() {
+    let server = server();
+    let mut api = api(&server, "expired", Duration::zero());
+
+    api.get_accounts(BUDGET).unwrap();
+    let requests = server.request_count();
+    api.get_accounts(BUDGET).unwrap();
+    assert_eq!(server.request_count(), requests + 1);
+}
+
+#[test]
+fn falls_back_to_stale_data_on_errors() {
+    let server = server();
+    let mut api = api(&server, "stale", Duration::zero());
+
+    let accounts = api.get_accounts(BUDGET).unwrap();
+    server.fail_next(500);
+    assert_eq!(api.get_accounts(BUDGET).unwrap(), accounts);
+    assert!(matches!(
+        api.take_stale(),
+        Some(DataError::Api { status: 500, .. })
+    ));
+}
+
+#[test]
+fn reports_bad_tokens() {
+    let server = server();
+    server.set_token("another-token");
+    let mut api = api(&server, "unauthorized", Duration::hours(1));
+
+    assert_eq!(api.get_budgets(), Err(DataError::Unauthorized));
+}
+
+#[test]
+fn reports_rate_limiting() {
+    let server = server();
+    server.set_rate_limit(1);
+    let mut api = api(&server, "rate_limit", Duration::hours(1));
+
+    api.get_budgets().unwrap();
+    assert_eq!(api.get_accounts(BUDGET), Err(DataError::RateLimited));
+}
+
+#[test]
+fn returns_deltas_since_server_knowledge() {
+    let server = server();
+    let config = config(&server);
+
+    let all = transactions_api::get_transactions(&config, BUDGET, None, None, None).unwrap();
+    let knowledge = all.data.server_knowledge;
+    let none =
+        transactions_api::get_transactions(&config, BUDGET, None, None, Some(knowledge)).unwrap();
+    assert!(none.data.transactions.is_empty());
+
+    let original = &all.data.transactions[0];
+    transactions_api::delete_transaction(&config, BUDGET, &original.id).unwrap();
+    assert!(server.server_knowledge() > knowledge);
+
+    let delta =
+        transactions_api::get_transactions(&config, BUDGET, None, None, Some(knowledge)).unwrap();
+    assert_eq!(delta.data.transactions.len(), 1);
+    assert!(delta.data.transactions[0].deleted);
+
+    let after = transactions_api::get_transactions(&config, BUDGET, None, None, None).unwrap();
+    assert_eq!(
+        after.data.transactions.len(),
+        all.data.transactions.len() - 1
+    );
+}
+
+#[test]
+fn replays_the_outbox_when_back_online() {
+    let server = server();
+    let mut api = api(&server, "outbox", Duration::hours(1));
+    let original = api.get_transactions_by_account(BUDGET, CHECKING).unwrap()[0].clone();
+
+    api.set_offline(true);
+    let edit = SaveTransaction {
+        memo: Some("edited offline".to_string()),
+        ..Default::default()
+    };
+    api.update_transaction(BUDGET, &original, edit).unwrap();
+    assert_eq!(api.status().pending, 1);
+
+    api.set_offline(false);
+    let transactions = api.get_transactions_by_account(BUDGET, CHECKING).unwrap();
+    assert_eq!(api.status().pending, 0);
+    assert_eq!(api.status().conflicts, 0);
+
+    let edited = transactions.iter().find(|t| t.id == original.id).unwrap();
+    assert_eq!(edited.memo.as_deref(), Some("edited offline"));
+}
+
+#[test]
+fn rejected_edits_leave_the_cache_alone() {
+    let server = server();
+    let mut api = api(&server, "rejected", Duration::hours(1));
+    let before = api.get_transactions_by_account(BUDGET, CHECKING).unwrap();
+
+    server.fail_next(400);
+    let edit = SaveTransaction {
+        memo: Some("rejected".to_string()),
+        ..Default::default()
+    };
+    let result = api.update_transaction(BUDGET, &before[0], edit);
+    assert!(matches!(result, Err(DataError::Api { status: 400, .. })));
+    assert_eq!(api.status().pending, 0);
+
+    let after = api.get_transactions_by_account(BUDGET, CHECKING).unwrap();
+    assert_eq!(after, before);
+}
+
+#[test]
+fn imports_statements_in_one_request_without_duplicates() {
+    let server = server();
+    let mut api = api(&server, "import", Duration::hours(1));
+    let before = api.get_transactions_by_account(BUDGET, CHECKING).unwrap();
+
+    let bank = ynab_tui::config::Bank::default();
+    let statement = import::read(Path::new("fixtures/statements/checking.csv"), &bank).unwrap();
+    let rows = import::preview(statement, &before);
+    let mut transactions = import::save_transactions(&rows, before[0].account_id);
+    // Already imported, YNAB has to skip it
+    transactions.push(SaveTransaction {
+        import_id: Some("YNAB:3200000:2024-05-01:1".to_string()),
+        ..transactions[0].clone()
+    });
+
+    let requests = server.request_count();
+    api.import_transactions(BUDGET, transactions).unwrap();
+    assert_eq!(server.request_count(), requests + 1);
+
+    let after = api.get_transactions_by_account(BUDGET, CHECKING).unwrap();
+    assert_eq!(after.len(), before.len() + 3);
+}
+
+#[test]
+fn detects_conflicting_edits() {
+    let server = server();
+    let mut api = api(&server, "conflict", Duration::hours(1));
+    let original = api.get_transactions_by_account(BUDGET, CHECKING).unwrap()[0].clone();
+
+    api.set_offline(true);
+    let edit = SaveTransaction {
+        memo: Some("edited offline".to_string()),
+        ..Default::default()
+    };
+    api.update_transaction(BUDGET, &original, edit).unwrap();
+
+    let elsewhere = PutTransactionWrapper {
+        transaction: Box::new(SaveTransaction {
+            memo: Some("edited elsewhere".to_string()),
+            ..Default::default()
+        }),
+    };
+    transactions_api::update_transaction(&config(&server), BUDGET, &original.id, elsewhere)
+        .unwrap();
+
+    api.set_offline(false);
+    api.get_budgets().unwrap();
+    assert_eq!(api.status().pending, 0);
+    assert_eq!(api.status().conflicts, 1);
+}
+
+#[test]
+fn asks_for_a_new_token_after_a_401() {
+    let server = server();
+    let cache_file =
+        std::env::temp_dir().join(format!("ynab_tui_reauth_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&cache_file);
+    let mut api = YnabApi::new(
+        "expired-token",
+        cache_file.to_str().unwrap(),
+        Duration::hours(1),
+    );
+    api.set_base_path(&server.base_path());
+    api.set_token_source(|| Ok(TOKEN.to_string()));
+
+    assert_eq!(api.get_budgets(), Err(DataError::Unauthorized));
+    assert!(api.get_budgets().is_ok());
+}
+
+#[test]
+fn reports_a_failing_token_source_until_it_works() {
+    let server = server();
+    server.set_token("another-token");
+    let mut api = api(&server, "token_source", Duration::hours(1));
+    let mut locked = true;
+    api.set_token_source(move || match std::mem::take(&mut locked) {
+        true => Err("gpg: no passphrase".to_string()),
+        false => Ok("another-token".to_string()),
+    });
+
+    assert_eq!(api.get_budgets(), Err(DataError::Unauthorized));
+    assert_eq!(
+        api.get_budgets(),
+        Err(DataError::Token("gpg: no passphrase".to_string()))
+    );
+    assert!(api.get_budgets().is_ok());
+}
+
+#[test]
+fn debug_output_leaves_out_the_token() {
+    let server = server();
+    let api = api(&server, "debug", Duration::hours(1));
+    assert!(!format!("{api:?}").contains(TOKEN));
+}
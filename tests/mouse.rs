@@ -0,0 +1,80 @@
+mod common;
+
+use common::Screen;
+use crossterm::event::{Event, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+/// The accounts page of the second budget
+fn screen() -> Screen {
+    let mut screen = Screen::new();
+    screen.press("j enter");
+    screen
+}
+
+fn mouse(screen: &mut Screen, kind: MouseEventKind, column: u16, row: u16) {
+    screen.send(Event::Mouse(MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    }));
+}
+
+fn click(screen: &mut Screen, column: u16, row: u16) {
+    mouse(screen, MouseEventKind::Down(MouseButton::Left), column, row);
+}
+
+// At 100x30 the page starts on row 3, the accounts are listed from row 4 and the transactions
+// from row 5, under the header
+
+#[test]
+fn clicking_an_account_selects_it() {
+    let mut screen = screen();
+    click(&mut screen, 5, 5);
+    assert_eq!(screen.app.path(), ["Homepage", "Savings"]);
+}
+
+#[test]
+fn scrolling_moves_the_selection() {
+    let mut screen = screen();
+    mouse(&mut screen, MouseEventKind::ScrollDown, 5, 10);
+    mouse(&mut screen, MouseEventKind::ScrollDown, 5, 10);
+    assert_eq!(screen.app.path(), ["Homepage", "Savings"]);
+
+    // Outside of the list nothing happens
+    mouse(&mut screen, MouseEventKind::ScrollUp, 5, 1);
+    assert_eq!(screen.app.path(), ["Homepage", "Savings"]);
+}
+
+#[test]
+fn double_clicking_a_transaction_opens_it_and_a_breadcrumb_goes_back() {
+    let mut screen = screen();
+    click(&mut screen, 30, 6);
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+    click(&mut screen, 30, 6);
+    assert_eq!(
+        screen.app.path(),
+        ["Homepage", "All Accounts", "Inspect Transaction"]
+    );
+
+    // `Homepage -> All Accounts -> Inspect Transaction` on the second header line
+    click(&mut screen, 14, 1);
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+    click(&mut screen, 3, 1);
+    assert_eq!(screen.app.path(), ["Homepage"]);
+}
+
+#[test]
+fn clicking_a_column_header_sorts_by_it() {
+    let mut screen = screen();
+    let header = screen.line(4);
+    let amount = header.chars().position(|c| c == 'A').unwrap() as u16;
+    assert!(header.contains("Amount"));
+
+    click(&mut screen, amount, 4);
+    assert!(screen.line(4).contains("▲Amount"));
+    assert!(screen.line(5).contains("Landlord"), "{}", screen.line(5));
+
+    click(&mut screen, amount, 4);
+    assert!(screen.line(4).contains("▼Amount"));
+    assert!(screen.line(5).contains("Acme Corp"), "{}", screen.line(5));
+}
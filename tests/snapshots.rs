@@ -0,0 +1,152 @@
+//! Renders pages into a `TestBackend` and compares the screen with `tests/snapshots/*.txt`.
+//! Run with `UPDATE_SNAPSHOTS=1` to write the current screens after an intended change.
+mod common;
+
+use common::Screen;
+use std::{fs, path::Path};
+use ynab_tui::event::ScriptedEvents;
+
+fn assert_snapshot(name: &str, screen: &mut Screen) {
+    let screen: String = screen
+        .lines()
+        .iter()
+        .map(|line| format!("{}\n", line.trim_end()))
+        .collect();
+
+    let path = Path::new("tests/snapshots").join(format!("{name}.txt"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &screen).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("{} is missing, run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert!(
+        screen == expected,
+        "{name} changed, run with UPDATE_SNAPSHOTS=1 if intended\n--- expected\n{expected}--- actual\n{screen}"
+    );
+}
+
+#[test]
+fn homepage() {
+    let mut screen = Screen::new();
+    assert_snapshot("homepage", &mut screen);
+}
+
+#[test]
+fn accounts_page() {
+    let mut screen = Screen::new();
+    screen.press("j enter");
+    assert_snapshot("accounts_page", &mut screen);
+}
+
+#[test]
+fn accounts_page_with_account_selected() {
+    let mut screen = Screen::new();
+    screen.press("j enter j");
+    assert_snapshot("accounts_page_account_selected", &mut screen);
+}
+
+#[test]
+fn accounts_page_filtered() {
+    let mut screen = Screen::new();
+    screen.press("j enter l /");
+    screen.type_text("corner");
+    screen.press("enter");
+    assert_snapshot("accounts_page_filtered", &mut screen);
+}
+
+#[test]
+fn accounts_page_help() {
+    let mut screen = Screen::new();
+    screen.press("j enter ctrl-h");
+    assert_snapshot("accounts_page_help", &mut screen);
+}
+
+#[test]
+fn accounts_page_detail_pane() {
+    let mut screen = Screen::new();
+    screen.press("j enter l j p");
+    assert_snapshot("accounts_page_detail_pane", &mut screen);
+}
+
+#[test]
+fn accounts_page_on_a_narrow_terminal() {
+    let mut screen = Screen::with_size(60, 30);
+    screen.press("j enter");
+    assert_snapshot("accounts_page_narrow", &mut screen);
+}
+
+#[test]
+fn transaction_page() {
+    let mut screen = Screen::new();
+    screen.press("j enter l j enter");
+    assert_snapshot("transaction_page", &mut screen);
+}
+
+#[test]
+fn back_returns_to_the_previous_page() {
+    let mut screen = Screen::new();
+    screen.press("j enter l j enter b h b k");
+    assert_snapshot("homepage", &mut screen);
+}
+
+#[test]
+fn run_stops_when_the_script_ends() {
+    let mut screen = Screen::new();
+    screen
+        .app
+        .run(&mut screen.terminal, &mut ScriptedEvents::keys("jk"))
+        .unwrap();
+    screen.app.settle();
+    assert_snapshot("homepage", &mut screen);
+}
+
+#[test]
+fn budget_command_switches_budgets() {
+    let mut screen = Screen::new();
+    screen.command("budget pers");
+    assert_snapshot("accounts_page", &mut screen);
+}
+
+#[test]
+fn budget_command_reports_unknown_budgets() {
+    let mut screen = Screen::new();
+    screen.command("budget nope");
+    assert_snapshot("budget_command_unknown", &mut screen);
+}
+
+#[test]
+fn import_page() {
+    let mut screen = Screen::new();
+    screen.command("budget pers");
+    screen.press("j");
+    screen.command("import fixtures/statements/checking.csv");
+    assert_snapshot("import_page", &mut screen);
+}
+
+#[test]
+fn import_page_created() {
+    let mut screen = Screen::new();
+    screen.command("budget pers");
+    screen.press("j");
+    screen.command("import fixtures/statements/checking.csv");
+    screen.press("c");
+    assert_snapshot("import_page_created", &mut screen);
+}
+
+#[test]
+fn account_finder() {
+    let mut screen = Screen::new();
+    screen.press("j enter f");
+    screen.type_text("ca");
+    assert_snapshot("account_finder", &mut screen);
+}
+
+#[test]
+fn tab_bar() {
+    let mut screen = Screen::new();
+    screen.press("j enter ctrl-t");
+    assert_snapshot("tab_bar", &mut screen);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::Screen;
+use tui::{backend::TestBackend, layout::Rect, Terminal};
+use ynab_openapi::models::TransactionDetail;
+use ynab_tui::{
+    components::{Column, StatefulTable},
+    data_layer::{BudgetDataSource, FixtureSource},
+};
+
+const PERSONAL: &str = "0bc3812b-ee6f-5610-a79d-5c8396015e74";
+
+/// The fixture transactions over and over, a day apart from 2000-01-01 on, numbered by id
+fn register(len: usize) -> Vec<TransactionDetail> {
+    let mut source = FixtureSource::load("fixtures").unwrap();
+    let transactions = source.get_transactions(PERSONAL).unwrap();
+    let start = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    (0..len)
+        .map(|i| {
+            let mut t = transactions[i % transactions.len()].clone();
+            t.id = i.to_string();
+            t.date = (start + chrono::Duration::days(i as i64)).to_string();
+            t
+        })
+        .collect()
+}
+
+fn selected(table: &StatefulTable<TransactionDetail>) -> usize {
+    table.selected().unwrap().id.parse().unwrap()
+}
+
+/// The lines of the table drawn at 80x20, where 17 rows fit under the header
+fn draw(table: &StatefulTable<TransactionDetail>) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+    terminal
+        .draw(|f| table.render(f, Rect::new(0, 0, 80, 20)))
+        .unwrap();
+    let buffer = terminal.backend().buffer();
+    let cells: Vec<&str> = buffer.content.iter().map(|c| c.symbol.as_str()).collect();
+    cells.chunks(80).map(|line| line.concat()).collect()
+}
+
+#[test]
+fn a_large_register_pages_and_jumps_to_the_ends() {
+    let mut table = StatefulTable::new();
+    table
+        .set_columns(&[Column::Date, Column::Payee])
+        .set_items(register(30_000));
+    draw(&table);
+
+    table.page_down();
+    assert_eq!(selected(&table), 0);
+    table.page_down();
+    assert_eq!(selected(&table), 17);
+
+    table.select_last();
+    let lines = draw(&table);
+    assert_eq!(selected(&table), 29_999);
+    assert!(lines[18].contains("2082-02-18"), "{}", lines[18]);
+
+    table.page_up();
+    assert_eq!(selected(&table), 29_982);
+    table.select_first();
+    table.page_up();
+    assert_eq!(selected(&table), 0);
+}
+
+#[test]
+fn select_date_picks_the_nearest_transaction() {
+    let mut table = StatefulTable::new();
+    table.set_items(register(100));
+
+    let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+    table.select_date(date("2000-02-01"));
+    assert_eq!(selected(&table), 31);
+    table.select_date(date("1990-01-01"));
+    assert_eq!(selected(&table), 0);
+    table.select_date(date("2030-01-01"));
+    assert_eq!(selected(&table), 99);
+}
+
+#[test]
+fn gg_goes_to_the_first_transaction_and_g_to_the_last() {
+    let mut screen = Screen::new();
+
+    // The Personal budget, with the table focused
+    screen.press("j enter l G");
+    let last = screen.highlighted();
+    assert!(!last.contains("Travel Agency"), "{last}");
+    screen.press("g");
+    assert_eq!(screen.highlighted(), last);
+    screen.press("g");
+    let first = screen.highlighted();
+    assert!(first.contains("Travel Agency"), "{first}");
+}
+
+#[test]
+fn moving_in_an_empty_table_selects_nothing() {
+    let mut table: StatefulTable<TransactionDetail> = StatefulTable::new();
+    assert_eq!(table.select_next(), None);
+    assert_eq!(table.select_prev(), None);
+    assert!(table.selected().is_none());
+}
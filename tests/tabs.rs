@@ -0,0 +1,48 @@
+mod common;
+
+use common::Screen;
+
+#[test]
+fn each_tab_keeps_its_own_pages() {
+    let mut screen = Screen::new();
+    screen.press("j enter j");
+    screen.press("ctrl-t enter");
+    assert_eq!(screen.app.tabs(), ["Checking", "All Accounts"]);
+    assert_eq!(screen.app.tab(), 1);
+
+    screen.press("]");
+    assert_eq!(screen.app.tab(), 0);
+    assert_eq!(screen.app.path(), ["Homepage", "Checking"]);
+
+    // Going back in one tab leaves the other alone
+    screen.press("b [");
+    assert_eq!(screen.app.tabs(), ["Homepage", "All Accounts"]);
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts"]);
+}
+
+#[test]
+fn tabnew_opens_a_budget() {
+    let mut screen = Screen::new();
+    screen.command("tabnew pers");
+    assert_eq!(screen.app.tabs(), ["Homepage", "All Accounts"]);
+}
+
+#[test]
+fn closes_every_tab_but_the_last() {
+    let mut screen = Screen::new();
+    screen.press("ctrl-t ctrl-t [ ctrl-w");
+    assert_eq!(screen.app.tabs().len(), 2);
+    assert_eq!(screen.app.tab(), 1);
+
+    screen.press("ctrl-w ctrl-w");
+    assert_eq!(screen.app.tabs(), ["Homepage"]);
+}
+
+#[test]
+fn tab_bar_lists_every_tab() {
+    let mut screen = Screen::new();
+    assert_eq!(screen.line(0).trim_end(), " 1 Homepage");
+
+    screen.press("j enter ctrl-t");
+    assert_eq!(screen.line(0).trim_end(), " 1 All Accounts │ 2 Homepage");
+}
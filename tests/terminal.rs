@@ -0,0 +1,18 @@
+use ynab_tui::terminal;
+
+#[test]
+fn restoring_twice_is_harmless() {
+    // The panic hook restores first, then the normal exit path does it again
+    let mut hook = vec![];
+    terminal::restore(&mut hook).unwrap();
+    let mut exit = vec![];
+    terminal::restore(&mut exit).unwrap();
+
+    assert_eq!(hook, exit);
+    let sequences = String::from_utf8(exit).unwrap();
+    assert!(
+        sequences.contains("\x1b[?1049l"),
+        "left the alternate screen"
+    );
+    assert!(sequences.contains("\x1b[?25h"), "showed the cursor");
+}
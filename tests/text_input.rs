@@ -0,0 +1,100 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ynab_tui::components::TextInput;
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+    Event::Key(KeyEvent::new(code, modifiers))
+}
+
+fn press(input: &mut TextInput, code: KeyCode) {
+    input.handle_event(&key(code, KeyModifiers::NONE));
+}
+
+fn ctrl(input: &mut TextInput, c: char) {
+    input.handle_event(&key(KeyCode::Char(c), KeyModifiers::CONTROL));
+}
+
+fn type_text(input: &mut TextInput, text: &str) {
+    for c in text.chars() {
+        press(input, KeyCode::Char(c));
+    }
+}
+
+#[test]
+fn inserts_at_the_cursor() {
+    let mut input = TextInput::new();
+    type_text(&mut input, "Cafe");
+    press(&mut input, KeyCode::Home);
+    type_text(&mut input, "Corner ");
+    press(&mut input, KeyCode::End);
+    press(&mut input, KeyCode::Backspace);
+    type_text(&mut input, "é");
+    assert_eq!(&*input, "Corner Café");
+
+    press(&mut input, KeyCode::Left);
+    press(&mut input, KeyCode::Left);
+    press(&mut input, KeyCode::Delete);
+    assert_eq!(&*input, "Corner Caé");
+}
+
+#[test]
+fn moves_and_deletes_by_word() {
+    let mut input = TextInput::new();
+    type_text(&mut input, "export ledger out.journal");
+
+    ctrl(&mut input, 'w');
+    assert_eq!(&*input, "export ledger out.");
+
+    input.handle_event(&key(KeyCode::Left, KeyModifiers::CONTROL));
+    input.handle_event(&key(KeyCode::Left, KeyModifiers::CONTROL));
+    assert_eq!(input.cursor_width(), "export ".len());
+    input.handle_event(&key(KeyCode::Char('f'), KeyModifiers::ALT));
+    assert_eq!(input.cursor_width(), "export ledger".len());
+
+    ctrl(&mut input, 'k');
+    assert_eq!(&*input, "export ledger");
+    ctrl(&mut input, 'u');
+    assert_eq!(&*input, "");
+}
+
+#[test]
+fn measures_the_cursor_in_terminal_columns() {
+    let mut input = TextInput::new();
+    // Wide characters take two columns, combining marks none
+    input.set("家計e\u{301}");
+    assert_eq!(input.cursor_width(), 5);
+
+    press(&mut input, KeyCode::Left);
+    assert_eq!(input.cursor_width(), 4);
+    press(&mut input, KeyCode::Backspace);
+    assert_eq!(&*input, "家e\u{301}");
+}
+
+#[test]
+fn pastes_on_one_line() {
+    let mut input = TextInput::new();
+    type_text(&mut input, "import ");
+    input.handle_event(&Event::Paste("statement.csv\n".to_string()));
+    assert_eq!(&*input, "import statement.csv ");
+}
+
+#[test]
+fn browses_the_committed_lines() {
+    let mut input = TextInput::new();
+    for line in ["budget Family", "export out.csv", "export out.csv"] {
+        input.set(line);
+        input.commit();
+        input.clear();
+    }
+    type_text(&mut input, "bud");
+
+    press(&mut input, KeyCode::Up);
+    assert_eq!(&*input, "export out.csv");
+    press(&mut input, KeyCode::Up);
+    assert_eq!(&*input, "budget Family");
+    press(&mut input, KeyCode::Up);
+    assert_eq!(&*input, "budget Family");
+
+    press(&mut input, KeyCode::Down);
+    press(&mut input, KeyCode::Down);
+    assert_eq!(&*input, "bud");
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::Screen;
+use std::collections::{BTreeMap, HashMap};
+use tui::style::{Color, Modifier, Style};
+use ynab_tui::theme::{parse_style, Theme};
+
+fn themes(name: &str, styles: &[(&str, &str)]) -> BTreeMap<String, HashMap<String, String>> {
+    let styles = styles
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    BTreeMap::from([(name.to_string(), styles)])
+}
+
+#[test]
+fn styles_are_parsed_from_words() {
+    assert_eq!(
+        parse_style("bold red on #102030").unwrap(),
+        Style::default()
+            .fg(Color::Red)
+            .bg(Color::Rgb(0x10, 0x20, 0x30))
+            .add_modifier(Modifier::BOLD)
+    );
+    assert_eq!(
+        parse_style("Light_Blue italic").unwrap(),
+        Style::default()
+            .fg(Color::LightBlue)
+            .add_modifier(Modifier::ITALIC)
+    );
+    assert_eq!(
+        parse_style("208").unwrap(),
+        Style::default().fg(Color::Indexed(208))
+    );
+    assert!(parse_style("blinking").is_err());
+    assert!(parse_style("red on").is_err());
+    assert_eq!(parse_style("#a€bc").unwrap_err(), "invalid color `#a€bc`");
+}
+
+#[test]
+fn a_user_theme_changes_its_base() {
+    let themes = themes("mine", &[("base", "light"), ("outflow", "bold magenta")]);
+    let theme = Theme::new(Some("mine"), &themes).unwrap();
+    assert_eq!(
+        theme.outflow,
+        Style::default()
+            .fg(Color::Magenta)
+            .add_modifier(Modifier::BOLD)
+    );
+    assert_eq!(theme.inflow, Theme::light().inflow);
+    assert_eq!(theme.amount(-1000), theme.outflow);
+
+    assert_eq!(Theme::new(None, &themes).unwrap(), Theme::dark());
+}
+
+#[test]
+fn unknown_themes_and_styles_are_errors() {
+    let themes = themes("mine", &[("outgoing", "red")]);
+    let error = Theme::new(Some("mine"), &themes).unwrap_err();
+    assert!(error.starts_with("Unknown style `outgoing` in [themes.mine]"));
+
+    let error = Theme::new(Some("solarized"), &themes).unwrap_err();
+    assert_eq!(
+        error,
+        "Unknown theme `solarized`, expected one of dark, light, mine"
+    );
+}
+
+#[test]
+fn the_table_colors_amounts_and_flags() {
+    let mut screen = Screen::new();
+    screen.press("j enter");
+    screen.app.draw(&mut screen.terminal).unwrap();
+
+    let theme = Theme::dark();
+    let buffer = screen.terminal.backend().buffer();
+    let find = |symbol: &str| {
+        let cell = buffer.content.iter().find(|c| c.symbol.starts_with(symbol));
+        cell.unwrap().style()
+    };
+    // Travel Agency, flagged purple, comes first
+    assert_eq!(find("⚑").fg, theme.flag_purple.fg);
+    assert_eq!(find("○").fg, theme.uncleared.fg);
+    assert_eq!(find("$").fg, theme.outflow.fg);
+}
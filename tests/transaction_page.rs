@@ -0,0 +1,131 @@
+mod common;
+
+use common::{FlakySource, Screen};
+use tui::{backend::TestBackend, layout::Rect, Terminal};
+use ynab_tui::components::render_fields;
+
+/// The first transaction of the Personal budget inspected: Travel Agency, uncleared and not
+/// approved yet
+fn screen() -> Screen {
+    let mut screen = Screen::new();
+    screen.press("j enter l j enter");
+    screen
+}
+
+#[test]
+fn quick_actions_clear_and_approve() {
+    let mut screen = screen();
+    assert!(screen.text().contains("Cleared   Uncleared"));
+
+    screen.press("c a");
+    let lines = screen.text();
+    assert!(lines.contains("Cleared   Cleared"), "{lines}");
+    assert!(lines.contains("Approved  yes"), "{lines}");
+
+    screen.press("a");
+    assert!(screen.text().contains("Already approved"));
+
+    // The register below was reloaded too
+    screen.press("b");
+    assert!(screen.text().contains("✓"));
+}
+
+#[test]
+fn saving_keeps_the_place_in_the_register() {
+    let mut screen = screen();
+    screen.press("c b");
+    assert!(screen.highlighted().contains("Travel Agency"));
+
+    screen.press("enter");
+    assert!(screen.text().contains("Cleared   Cleared"));
+}
+
+#[test]
+fn retrying_a_failed_save_finishes_it() {
+    let mut screen = Screen::with_source(FlakySource::new(1));
+    screen.press("j enter l j enter c");
+    assert!(screen.text().contains("YNAB error 400: rejected"));
+
+    screen.press("r");
+    let lines = screen.lines();
+    let title = lines
+        .iter()
+        .find(|l| l.starts_with("┌Transaction"))
+        .unwrap();
+    assert!(title.starts_with("┌Transaction──"), "still saving: {title}");
+    assert!(lines.concat().contains("Cleared   Cleared"));
+}
+
+#[test]
+fn the_edit_form_saves_what_changed() {
+    let mut screen = screen();
+    screen.press("e");
+    screen.type_text("x");
+    screen.press("tab tab tab tab");
+    screen.type_text(" paid");
+    screen.press("enter");
+    assert!(screen
+        .text()
+        .contains("`2024-05-18x` is not a date like 2024-05-31"));
+
+    screen.press("esc");
+    assert!(screen.text().contains("Flights deposit"));
+    screen.press("e tab tab tab");
+    screen.type_text(" paid");
+    screen.press("enter");
+    let lines = screen.text();
+    assert!(lines.contains("Memo      Flights deposit paid"), "{lines}");
+    assert!(lines.contains("Amount    $-520.00"), "{lines}");
+}
+
+#[test]
+fn the_edit_form_picks_the_payee_and_category() {
+    let mut screen = screen();
+    screen.press("e tab enter");
+    screen.type_text("corner");
+    screen.press("enter tab");
+    screen.type_text("dining");
+    assert!(screen.text().contains(">> Everyday: Dining Out"));
+    screen.press("enter tab enter");
+    let lines = screen.text();
+    assert!(lines.contains("Payee     Corner Cafe"), "{lines}");
+    assert!(lines.contains("Category  Dining Out"), "{lines}");
+
+    // A payee that matches nothing is created
+    screen.press("e tab");
+    screen.type_text("New Shop");
+    screen.press("enter tab tab enter");
+    assert!(screen.text().contains("Payee     New Shop"));
+}
+
+#[test]
+fn deleting_asks_first() {
+    let mut screen = screen();
+    screen.press("d");
+    assert!(screen.text().contains("Delete 2024-05-18 Travel Agency"));
+    screen.press("esc");
+    assert!(!screen.text().contains("(deleted)"));
+
+    screen.press("d enter");
+    assert!(screen.text().contains("Transaction (deleted)"));
+    screen.press("b");
+    assert!(!screen.text().contains("Travel Agency"));
+}
+
+#[test]
+fn long_values_wrap_beside_their_label() {
+    let mut terminal = Terminal::new(TestBackend::new(30, 6)).unwrap();
+    let fields = [
+        ("Memo", "deposit for the flights to Lisbon".to_string()),
+        ("Amount", "$-520.00".to_string()),
+    ];
+    terminal
+        .draw(|f| render_fields(f, Rect::new(0, 0, 30, 6), "Transaction", &fields))
+        .unwrap();
+    let buffer = terminal.backend().buffer();
+    let cells: Vec<&str> = buffer.content.iter().map(|c| c.symbol.as_str()).collect();
+    let lines: Vec<String> = cells.chunks(30).map(|line| line.concat()).collect();
+    assert_eq!(lines[1], "│Memo    deposit for the     │");
+    assert_eq!(lines[2], "│        flights to Lisbon   │");
+    assert_eq!(lines[3], "│Amount  $-520.00            │");
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::Screen;
+use ynab_tui::data_layer::FixtureSource;
+
+/// The second transaction of the Personal budget selected, the Savings side of a transfer from
+/// Checking
+fn screen() -> Screen {
+    let mut screen = Screen::new();
+    screen.press("j enter l j j");
+    screen
+}
+
+#[test]
+fn a_transfer_leads_to_its_other_side() {
+    let mut screen = screen();
+    assert!(screen.highlighted().contains("Transfer : Check"));
+
+    screen.press("t");
+    assert_eq!(screen.app.path(), ["Homepage", "All Accounts", "Checking"]);
+    assert!(screen.highlighted().contains("Transfer : Savin"));
+
+    // And back again
+    screen.press("t");
+    assert_eq!(
+        screen.app.path(),
+        ["Homepage", "All Accounts", "Checking", "Savings"]
+    );
+    assert!(screen.highlighted().contains("Transfer : Check"));
+}
+
+#[test]
+fn the_inspect_page_shows_both_sides() {
+    let mut screen = screen();
+    screen.press("enter");
+    let lines = screen.lines();
+    assert!(lines.iter().any(|l| l.contains("Other side")));
+    let accounts = lines.iter().find(|l| l.contains("Savings")).unwrap();
+    assert!(accounts.contains("Checking"), "{accounts}");
+
+    screen.press("t");
+    assert_eq!(
+        screen.app.path(),
+        [
+            "Homepage",
+            "All Accounts",
+            "Inspect Transaction",
+            "Checking"
+        ]
+    );
+}
+
+#[test]
+fn a_missing_account_is_reported() {
+    let mut budgets = FixtureSource::load_budgets("fixtures").unwrap();
+    let personal = budgets.iter_mut().find(|b| b.budget.name == "Personal");
+    personal.unwrap().accounts.retain(|a| a.name != "Checking");
+    let mut screen = Screen::with_budgets(budgets);
+    screen.press("j enter l j j t");
+    assert!(screen.text().contains("Transfer account not found"));
+
+    screen.press("esc");
+    let lines = screen.lines();
+    let title = lines.iter().find(|l| l.contains("Transactions")).unwrap();
+    assert!(title.contains("┌Transactions─"), "still loading: {title}");
+}
@@ -0,0 +1,41 @@
+use ynab_tui::data_layer::{DataError, FixtureSource, Payload, Request, Worker};
+
+const PERSONAL: &str = "0bc3812b-ee6f-5610-a79d-5c8396015e74";
+
+fn worker() -> Worker {
+    Worker::spawn(FixtureSource::load("fixtures").unwrap())
+}
+
+#[test]
+fn answers_with_the_request() {
+    let mut worker = worker();
+    let request = Request::Accounts {
+        budget_id: PERSONAL.to_string(),
+    };
+    worker.request(request.clone());
+    assert!(worker.is_busy());
+
+    let response = worker.recv().unwrap();
+    assert_eq!(response.request, request);
+    match response.payload {
+        Ok(Payload::Accounts(accounts)) => assert!(accounts.iter().any(|a| a.name == "Checking")),
+        payload => panic!("expected accounts, got {payload:?}"),
+    }
+    assert!(!worker.is_busy());
+    assert!(worker.recv().is_none());
+}
+
+#[test]
+fn is_not_busy_after_an_error() {
+    let mut worker = worker();
+    worker.request(Request::Transactions {
+        budget_id: "no-such-budget".to_string(),
+    });
+
+    let response = worker.recv().unwrap();
+    assert!(matches!(
+        response.payload,
+        Err(DataError::Api { status: 404, .. })
+    ));
+    assert!(!worker.is_busy());
+}